@@ -55,8 +55,10 @@ mod tests {
     #[test]
     fn test_person_changelog_sandbox() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
-        let mut client_options = PDLCLientOptions::default();
-        client_options.sandbox = true;
+        let client_options = PDLCLientOptions {
+            sandbox: true,
+            ..PDLCLientOptions::default()
+        };
         let client = PDLClient::new(&api_key).options(client_options).build();
 
         let changelog = Changelog { client };
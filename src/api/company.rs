@@ -1,14 +1,20 @@
+use reqwest::StatusCode;
+
 use crate::{
     models::{
-        common::SearchParams,
+        common::{into_batches, BulkResult, BulkValidation, SearchParams, BULK_MAX_REQUESTS},
         company::{
-            BulkCompanyEnrichResponse, BulkEnrichCompanyParams, CleanCompanyParams,
-            CleanCompanyResponse, CompanyResponse, EnrichCompanyParams, SearchCompanyResponse,
+            BulkCompanyEnrichResponse, BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams,
+            CleanCompanyParams, CleanCompanyResponse, CleanEnrichCompanyResponse, CompanyParams,
+            CompanyResponse, EnrichCompanyParams, SearchCompanyResponse,
         },
     },
-    PDLClient, PDLError,
+    EnrichmentLedger, PDLClient, PDLError,
 };
 
+/// Keeps company fingerprints apart from person ones in the ledger.
+const COMPANY_LEDGER_KIND: &str = "company";
+
 pub(crate) static ENRICH_PATH: &str = "/company/enrich";
 pub(crate) static COMPANY_BULK_ENRICH_PATH: &str = "/company/enrich/bulk";
 pub(crate) static SEARCH_PATH: &str = "/company/search";
@@ -22,22 +28,104 @@ impl Company {
     /// Enrich a company
     /// docs: https://docs.peopledatalabs.com/docs/company-enrichment-api
     pub fn enrich(&self, params: EnrichCompanyParams) -> Result<CompanyResponse, PDLError> {
+        self.enrich_traced(params).map(|(response, _)| response)
+    }
+
+    /// `enrich`, also telling whether the response was replayed from the
+    /// client's cache or ledger rather than sent.
+    pub(crate) fn enrich_traced(
+        &self,
+        params: EnrichCompanyParams,
+    ) -> Result<(CompanyResponse, bool), PDLError> {
         params.validate()?;
-        self.client
-            .get::<CompanyResponse, EnrichCompanyParams>(ENRICH_PATH, params)
+        let fingerprint = match self.client.ledger() {
+            Some(_) => Some(EnrichmentLedger::fingerprint(COMPANY_LEDGER_KIND, &params)?),
+            None => None,
+        };
+        let send = || {
+            self.client
+                .get_traced::<CompanyResponse, EnrichCompanyParams>(ENRICH_PATH, params, None)
+        };
+        match (self.client.ledger(), fingerprint) {
+            (Some(ledger), Some(fingerprint)) => ledger.run(COMPANY_LEDGER_KIND, fingerprint, send),
+            _ => send(),
+        }
     }
 
-    /// Bulk Enrich for Companies
+    /// Bulk Enrich for Companies. The batch is rejected without being sent
+    /// if any request fails validation. More than 100 requests are sent in
+    /// several calls and the responses concatenated in request order.
     /// docs: https://docs.peopledatalabs.com/docs/bulk-company-enrichment-api
     pub fn bulk_enrich(
         &self,
         params: BulkEnrichCompanyParams,
     ) -> Result<Vec<BulkCompanyEnrichResponse>, PDLError> {
-        self.client
-            .post::<Vec<BulkCompanyEnrichResponse>, BulkEnrichCompanyParams>(
-                COMPANY_BULK_ENRICH_PATH,
-                params,
-            )
+        self.bulk_enrich_with_policy(params, BulkValidation::RejectBatch)
+            .map(|result| result.responses)
+    }
+
+    /// Bulk Enrich for Companies, choosing how requests that fail validation
+    /// are handled. With `BulkValidation::DropInvalid` the valid requests are
+    /// still sent and the invalid ones are returned in `rejected`.
+    pub fn bulk_enrich_with_policy(
+        &self,
+        params: BulkEnrichCompanyParams,
+        policy: BulkValidation,
+    ) -> Result<BulkResult<BulkEnrichSingleCompanyParams, BulkCompanyEnrichResponse>, PDLError>
+    {
+        let (requests, mut result) =
+            BulkResult::partition(params.requests, policy, |request| request.validate())?;
+
+        let send = |requests: Vec<BulkEnrichSingleCompanyParams>| {
+            let mut responses = Vec::new();
+            for requests in into_batches(requests, BULK_MAX_REQUESTS) {
+                let batch = BulkEnrichCompanyParams {
+                    base_params: params.base_params.clone(),
+                    requests,
+                    additional_params: params.additional_params.clone(),
+                };
+                responses.extend(
+                    self.client
+                        .post::<Vec<BulkCompanyEnrichResponse>, BulkEnrichCompanyParams>(
+                            COMPANY_BULK_ENRICH_PATH,
+                            batch,
+                        )?,
+                );
+            }
+            Ok(responses)
+        };
+        result.responses = match self.client.ledger() {
+            Some(ledger) => {
+                // Fingerprinted like a single enrichment with the batch's
+                // options.
+                let requests = requests
+                    .into_iter()
+                    .map(|request| {
+                        let input = EnrichCompanyParams {
+                            base_params: params.base_params.clone(),
+                            company_params: request.params.clone(),
+                            additional_params: params.additional_params.clone(),
+                        };
+                        Ok((
+                            EnrichmentLedger::fingerprint(COMPANY_LEDGER_KIND, &input)?,
+                            request,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, PDLError>>()?;
+                ledger.run_bulk(
+                    COMPANY_LEDGER_KIND,
+                    requests,
+                    |request, mut response: BulkCompanyEnrichResponse| {
+                        response.metadata = request.metadata.clone();
+                        response
+                    },
+                    send,
+                )?
+            }
+            None => send(requests)?,
+        };
+
+        Ok(result)
     }
 
     /// Search gives you access to every record in our full Company dataset,
@@ -46,7 +134,7 @@ impl Company {
     pub fn search(&self, params: SearchParams) -> Result<SearchCompanyResponse, PDLError> {
         params.validate()?;
         self.client
-            .get::<SearchCompanyResponse, SearchParams>(SEARCH_PATH, params)
+            .get_or_post::<SearchCompanyResponse, SearchParams>(SEARCH_PATH, params, None)
     }
 
     /// Clean your company data, so you can better query our person data
@@ -56,18 +144,97 @@ impl Company {
         self.client
             .get::<CleanCompanyResponse, CleanCompanyParams>(CLEAN_PATH, params)
     }
+
+    /// Cleans a messy name, website or profile, then enriches the company
+    /// the cleaner resolved by its PDL id, website and LinkedIn URL. The raw
+    /// input is enriched instead when the cleaner finds nothing. A 404 from
+    /// either call leaves that half of the response `None`.
+    pub fn clean_and_enrich(
+        &self,
+        params: CleanCompanyParams,
+    ) -> Result<CleanEnrichCompanyResponse, PDLError> {
+        clean_then_enrich(
+            params,
+            |params| self.clean(params),
+            |params| self.enrich(params),
+        )
+    }
+}
+
+/// Turns a 404 into `None`.
+fn found<T>(result: Result<T, PDLError>) -> Result<Option<T>, PDLError> {
+    match result {
+        Ok(response) => Ok(Some(response)),
+        Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn clean_then_enrich<C, E>(
+    params: CleanCompanyParams,
+    clean: C,
+    enrich: E,
+) -> Result<CleanEnrichCompanyResponse, PDLError>
+where
+    C: FnOnce(CleanCompanyParams) -> Result<CleanCompanyResponse, PDLError>,
+    E: FnOnce(EnrichCompanyParams) -> Result<CompanyResponse, PDLError>,
+{
+    params.validate()?;
+    let raw = CompanyParams {
+        name: params.name.clone(),
+        website: params.website.clone(),
+        profile: params.profile.clone(),
+        ..CompanyParams::default()
+    };
+
+    let cleaned = found(clean(params))?;
+    let company_params = match &cleaned {
+        Some(cleaned)
+            if cleaned.id.is_some()
+                || cleaned.website.is_some()
+                || cleaned.linkedin_url.is_some() =>
+        {
+            CompanyParams {
+                pdl_id: cleaned.id.clone(),
+                website: cleaned.website.clone(),
+                profile: cleaned.linkedin_url.clone(),
+                ..CompanyParams::default()
+            }
+        }
+        _ => raw,
+    };
+    let enriched = found(enrich(EnrichCompanyParams {
+        base_params: None,
+        company_params,
+        additional_params: None,
+    }))?;
+
+    Ok(CleanEnrichCompanyResponse {
+        confidence: cleaned
+            .as_ref()
+            .map_or(0.0, CleanCompanyResponse::confidence),
+        clean: cleaned,
+        enrich: enriched,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         client::PDLClient,
-        models::company::{BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams},
+        models::{
+            common::{into_batches, BulkValidation, BULK_MAX_REQUESTS},
+            company::{BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams, CompanyMetadata},
+        },
         BaseParams, CleanCompanyParams, CompanyParams, EnrichCompanyParams, SearchBaseParams,
         SearchParams,
     };
 
-    use super::Company;
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use super::{clean_then_enrich, Company};
+    use crate::PDLError;
 
     #[test]
     fn test_company_enrich() {
@@ -106,27 +273,148 @@ mod tests {
         let mut company_params_2 = CompanyParams::default();
         company_params_2.website = Some("google.com".to_string());
 
+        let mut metadata = CompanyMetadata::new();
+        metadata.insert("crm_id".to_string(), "42".to_string());
+
         let bulk_enrich_single_company_params_1 = BulkEnrichSingleCompanyParams {
             params: company_params_1,
+            metadata: Some(metadata.clone()),
         };
 
         let bulk_enrich_single_company_params_2 = BulkEnrichSingleCompanyParams {
             params: company_params_2,
+            metadata: None,
         };
 
         let bulk_enrich_params = BulkEnrichCompanyParams {
+            base_params: None,
             requests: vec![
                 bulk_enrich_single_company_params_1,
                 bulk_enrich_single_company_params_2,
             ],
+            additional_params: None,
         };
 
         let resp = company.bulk_enrich(bulk_enrich_params).expect("ERROR");
 
         assert_eq!(resp[0].status, 200);
+        assert_eq!(resp[0].metadata, Some(metadata));
         assert_eq!(resp[1].status, 200);
     }
 
+    #[test]
+    fn test_bulk_company_enrich_validates_before_sending() {
+        let client = PDLClient::new("offline").build();
+        let company = Company { client };
+
+        let empty = || BulkEnrichSingleCompanyParams {
+            params: CompanyParams::default(),
+            metadata: None,
+        };
+
+        let params = BulkEnrichCompanyParams {
+            requests: vec![empty()],
+            ..BulkEnrichCompanyParams::default()
+        };
+        assert!(company.bulk_enrich(params).is_err());
+
+        let params = BulkEnrichCompanyParams {
+            requests: vec![empty(), empty()],
+            ..BulkEnrichCompanyParams::default()
+        };
+        let result = company
+            .bulk_enrich_with_policy(params, BulkValidation::DropInvalid)
+            .expect("ERROR");
+        assert!(result.responses.is_empty());
+        assert_eq!(result.rejected.len(), 2);
+    }
+
+    #[test]
+    fn test_bulk_company_enrich_params_serialization() {
+        let mut metadata = CompanyMetadata::new();
+        metadata.insert("crm_id".to_string(), "42".to_string());
+
+        let params = BulkEnrichCompanyParams::builder()
+            .request_with_metadata(
+                CompanyParams::builder()
+                    .website("google.com")
+                    .build()
+                    .unwrap(),
+                metadata,
+            )
+            .required("website")
+            .titlecase(true)
+            .build()
+            .expect("ERROR");
+
+        let body = serde_json::to_value(&params).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "requests": [{
+                    "params": { "website": "google.com" },
+                    "metadata": { "crm_id": "42" },
+                }],
+                "required": "website",
+                "titlecase": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_company_params_canonicalize_profile() {
+        let mut params = CompanyParams::builder()
+            .profile("https://www.linkedin.com/company/PeopleDataLabs/?trk=x")
+            .build()
+            .expect("ERROR");
+        params.canonicalize_profile().expect("ERROR");
+        assert_eq!(
+            params.profile.as_deref(),
+            Some("linkedin.com/company/peopledatalabs")
+        );
+
+        let mut params = CleanCompanyParams::builder()
+            .profile("https://www.linkedin.com/in/seanthorne")
+            .build()
+            .expect("ERROR");
+        assert!(params.canonicalize_profile().is_err());
+        assert_eq!(
+            params.profile.as_deref(),
+            Some("https://www.linkedin.com/in/seanthorne")
+        );
+    }
+
+    #[test]
+    fn test_company_params_fill_address() {
+        let mut params = CompanyParams::builder()
+            .name("people data labs")
+            .build()
+            .expect("ERROR");
+        params
+            .fill_address("455 Market St, San Francisco, CA 94105")
+            .expect("ERROR");
+        assert_eq!(params.street_address.as_deref(), Some("455 Market St"));
+        assert_eq!(params.locality.as_deref(), Some("San Francisco"));
+        assert_eq!(params.region.as_deref(), Some("california"));
+        assert_eq!(params.country.as_deref(), Some("united states"));
+        assert_eq!(params.postal_code.as_deref(), Some("94105"));
+        assert_eq!(
+            params.location,
+            Some(vec!["455 Market St, San Francisco, CA 94105".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_into_batches() {
+        let batches = into_batches((0..250).collect(), BULK_MAX_REQUESTS);
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![100, 100, 50]
+        );
+        assert_eq!(batches[2][0], 200);
+        assert!(into_batches(Vec::<i32>::new(), 100).is_empty());
+    }
+
     #[test]
     fn test_company_clean() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
@@ -143,6 +431,100 @@ mod tests {
         assert_eq!(resp.name, Some("google".to_string()));
     }
 
+    #[test]
+    fn test_clean_then_enrich_uses_cleaned_identifiers() {
+        let clean_params = CleanCompanyParams {
+            name: Some("Google Inc.".to_string()),
+            ..Default::default()
+        };
+
+        let resp = clean_then_enrich(
+            clean_params,
+            |params| {
+                assert_eq!(params.name.as_deref(), Some("Google Inc."));
+                Ok(serde_json::from_value(json!({
+                    "status": 200,
+                    "name": "google",
+                    "id": "aKCIYBNF9ey6o5CjHCCO4goHYKlf",
+                    "website": "google.com",
+                    "linkedin_url": "linkedin.com/company/google",
+                    "score": 8.0,
+                    "fuzzy_match": true,
+                }))
+                .expect("ERROR"))
+            },
+            |params| {
+                assert_eq!(
+                    params.company_params.pdl_id.as_deref(),
+                    Some("aKCIYBNF9ey6o5CjHCCO4goHYKlf")
+                );
+                assert_eq!(params.company_params.website.as_deref(), Some("google.com"));
+                assert_eq!(
+                    params.company_params.profile.as_deref(),
+                    Some("linkedin.com/company/google")
+                );
+                assert!(params.company_params.name.is_none());
+                Ok(
+                    serde_json::from_value(json!({"status": 200, "name": "google"}))
+                        .expect("ERROR"),
+                )
+            },
+        )
+        .expect("ERROR");
+
+        assert_eq!(resp.confidence, 4.0);
+        assert!(resp.clean.is_some());
+        assert_eq!(
+            resp.enrich.and_then(|company| company.name).as_deref(),
+            Some("google")
+        );
+    }
+
+    #[test]
+    fn test_clean_then_enrich_falls_back_to_raw_input() {
+        let clean_params = CleanCompanyParams {
+            website: Some("example.invalid".to_string()),
+            ..Default::default()
+        };
+
+        let resp = clean_then_enrich(
+            clean_params,
+            |_| Err(PDLError::HTTPError(StatusCode::NOT_FOUND)),
+            |params| {
+                assert_eq!(
+                    params.company_params.website.as_deref(),
+                    Some("example.invalid")
+                );
+                Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+            },
+        )
+        .expect("ERROR");
+
+        assert!(resp.clean.is_none());
+        assert!(resp.enrich.is_none());
+        assert_eq!(resp.confidence, 0.0);
+    }
+
+    #[test]
+    fn test_enrich_company_params_builder() {
+        assert!(EnrichCompanyParams::builder()
+            .country("united states")
+            .build()
+            .is_err());
+
+        let params = EnrichCompanyParams::builder()
+            .website("google.com")
+            .titlecase(true)
+            .build()
+            .expect("ERROR");
+
+        assert_eq!(
+            params.company_params.website,
+            Some("google.com".to_string())
+        );
+        assert_eq!(params.additional_params.unwrap().titlecase, Some(true));
+    }
+
     #[test]
     fn test_company_search() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
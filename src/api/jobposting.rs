@@ -124,6 +124,23 @@ mod tests {
         assert!(resp.is_err());
     }
 
+    #[test]
+    fn test_job_posting_search_params_builder() {
+        assert!(JobPostingSearchParams::builder().size(101).build().is_err());
+
+        let params = JobPostingSearchParams::builder()
+            .size(3)
+            .title_role("engineering")
+            .remote_work_policy(RemoteWorkPolicy::Remote)
+            .build()
+            .expect("ERROR");
+
+        assert_eq!(
+            params.search_base_params.title_role,
+            Some("engineering".to_string())
+        );
+    }
+
     #[test]
     fn test_job_posting_is_active_serialization_opt_in() {
         // None must be omitted entirely; Some(false) must round-trip.
@@ -1,16 +1,21 @@
+use serde_json::Value;
+
 use crate::{
     models::{
-        common::SearchParams,
+        common::{into_batches, BulkResult, BulkValidation, SearchParams, BULK_MAX_REQUESTS},
         person::{
-            BulkEnrichPersonParams, BulkEnrichPersonResponse, BulkRetrievePersonParams,
-            BulkRetrievePersonResponse, EnrichPersonParams, EnrichPersonResponse,
-            IdentifyPersonParams, IdentifyPersonResponse, RetrievePersonParams,
-            RetrievePersonResponse, SearchPersonResponse,
+            BulkEnrichPersonParams, BulkEnrichPersonResponse, BulkEnrichSinglePersonParams,
+            BulkRetrievePersonParams, BulkRetrievePersonResponse, BulkRetrieveSinglePersonParams,
+            EnrichPersonParams, EnrichPersonResponse, IdentifyPersonParams, IdentifyPersonResponse,
+            PersonParams, RetrievePersonParams, RetrievePersonResponse, SearchPersonResponse,
         },
     },
-    PDLClient, PDLError,
+    EnrichmentLedger, PDLClient, PDLError,
 };
 
+/// Keeps person fingerprints apart from company ones in the ledger.
+const PERSON_LEDGER_KIND: &str = "person";
+
 pub(crate) static PERSON_ENRICH_PATH: &str = "/person/enrich";
 pub(crate) static PERSON_BULK_ENRICH_PATH: &str = "/person/bulk";
 pub(crate) static PERSON_IDENTIFY_PATH: &str = "/person/identify";
@@ -23,36 +28,142 @@ pub struct Person {
 }
 
 impl Person {
+    /// Enrich a person. Phones and emails are normalized first as the
+    /// client's `phone_region` and `email_hashing` options specify.
     pub fn enrich(&self, params: EnrichPersonParams) -> Result<EnrichPersonResponse, PDLError> {
+        self.enrich_traced(params).map(|(response, _)| response)
+    }
+
+    /// `enrich`, also telling whether the response was replayed from the
+    /// client's cache or ledger rather than sent.
+    pub(crate) fn enrich_traced(
+        &self,
+        mut params: EnrichPersonParams,
+    ) -> Result<(EnrichPersonResponse, bool), PDLError> {
+        self.prepare(&mut params.person_params)?;
         params.validate()?;
-        self.client
-            .get::<EnrichPersonResponse, EnrichPersonParams>(PERSON_ENRICH_PATH, params)
+        let encoding = params.person_params.query_encoding.clone();
+        let fingerprint = match self.client.ledger() {
+            Some(_) => Some(EnrichmentLedger::fingerprint(PERSON_LEDGER_KIND, &params)?),
+            None => None,
+        };
+        let send = || {
+            self.client
+                .get_or_post_traced::<EnrichPersonResponse, EnrichPersonParams>(
+                    PERSON_ENRICH_PATH,
+                    params,
+                    Some(&encoding),
+                )
+        };
+        match (self.client.ledger(), fingerprint) {
+            (Some(ledger), Some(fingerprint)) => ledger.run(PERSON_LEDGER_KIND, fingerprint, send),
+            _ => send(),
+        }
     }
 
+    /// Bulk enrich people. Every request is validated first and the batch
+    /// is rejected without being sent if any of them is invalid. More than
+    /// 100 requests are sent in several calls and the responses
+    /// concatenated in request order.
     pub fn bulk_enrich(
         &self,
         params: BulkEnrichPersonParams,
     ) -> Result<Vec<BulkEnrichPersonResponse>, PDLError> {
-        self.client
-            .post::<Vec<BulkEnrichPersonResponse>, BulkEnrichPersonParams>(
-                PERSON_BULK_ENRICH_PATH,
-                params,
-            )
+        self.bulk_enrich_with_policy(params, BulkValidation::RejectBatch)
+            .map(|result| result.responses)
     }
 
+    /// Bulk enrich people, choosing how requests that fail validation are
+    /// handled. With `BulkValidation::DropInvalid` the valid requests are
+    /// still sent and the invalid ones are returned in `rejected`.
+    pub fn bulk_enrich_with_policy(
+        &self,
+        params: BulkEnrichPersonParams,
+        policy: BulkValidation,
+    ) -> Result<BulkResult<BulkEnrichSinglePersonParams, BulkEnrichPersonResponse>, PDLError> {
+        // Requests whose phones or emails can't be normalized fail
+        // validation. A rejected request keeps its params as given.
+        let (requests, mut result) = BulkResult::partition(params.requests, policy, |request| {
+            request.validate()?;
+            let mut prepared = request.params.clone();
+            self.prepare(&mut prepared)?;
+            request.params = prepared;
+            Ok(())
+        })?;
+
+        let send = |requests: Vec<BulkEnrichSinglePersonParams>| {
+            let mut responses = Vec::new();
+            for requests in into_batches(requests, BULK_MAX_REQUESTS) {
+                let batch = BulkEnrichPersonParams {
+                    requires: params.requires.clone(),
+                    requests,
+                };
+                responses.extend(
+                    self.client
+                        .post::<Vec<BulkEnrichPersonResponse>, BulkEnrichPersonParams>(
+                            PERSON_BULK_ENRICH_PATH,
+                            batch,
+                        )?,
+                );
+            }
+            Ok(responses)
+        };
+        result.responses = match self.client.ledger() {
+            Some(ledger) => {
+                // Fingerprinted like a single enrichment with `required` set.
+                let requests = requests
+                    .into_iter()
+                    .map(|request| {
+                        let mut input = serde_json::to_value(&request.params)
+                            .map_err(|_| PDLError::SerializationError)?;
+                        if let (Value::Object(input), Some(requires)) =
+                            (&mut input, &params.requires)
+                        {
+                            input.insert("required".to_string(), requires.clone().into());
+                        }
+                        Ok((
+                            EnrichmentLedger::fingerprint(PERSON_LEDGER_KIND, &input)?,
+                            request,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, PDLError>>()?;
+                ledger.run_bulk(
+                    PERSON_LEDGER_KIND,
+                    requests,
+                    |request, mut response: BulkEnrichPersonResponse| {
+                        response.metadata = request.metadata.clone();
+                        response
+                    },
+                    send,
+                )?
+            }
+            None => send(requests)?,
+        };
+
+        Ok(result)
+    }
+
+    /// Identify a person. Phones and emails are normalized first as for
+    /// `enrich`.
     pub fn identify(
         &self,
-        params: IdentifyPersonParams,
+        mut params: IdentifyPersonParams,
     ) -> Result<IdentifyPersonResponse, PDLError> {
+        self.prepare(&mut params.person_params)?;
         params.validate()?;
+        let encoding = params.person_params.query_encoding.clone();
         self.client
-            .get::<IdentifyPersonResponse, IdentifyPersonParams>(PERSON_IDENTIFY_PATH, params)
+            .get_with_encoding::<IdentifyPersonResponse, IdentifyPersonParams>(
+                PERSON_IDENTIFY_PATH,
+                params,
+                &encoding,
+            )
     }
 
     pub fn search(&self, params: SearchParams) -> Result<SearchPersonResponse, PDLError> {
         params.validate()?;
         self.client
-            .get::<SearchPersonResponse, SearchParams>(PERSON_SEARCH_PATH, params)
+            .get_or_post::<SearchPersonResponse, SearchParams>(PERSON_SEARCH_PATH, params, None)
     }
 
     pub fn retrieve(
@@ -65,29 +176,76 @@ impl Person {
             .get::<RetrievePersonResponse, RetrievePersonParams>(&url, params)
     }
 
+    /// Bulk retrieve people by PDL ID. The batch is validated first and
+    /// rejected without being sent if any request is invalid.
     pub fn bulk_retrieve(
         &self,
         params: BulkRetrievePersonParams,
     ) -> Result<Vec<BulkRetrievePersonResponse>, PDLError> {
-        self.client
-            .post::<Vec<BulkRetrievePersonResponse>, BulkRetrievePersonParams>(
-                PERSON_BULK_RETRIEVE_PATH,
-                params,
-            )
+        self.bulk_retrieve_with_policy(params, BulkValidation::RejectBatch)
+            .map(|result| result.responses)
+    }
+
+    /// Bulk retrieve people, choosing how requests that fail validation are
+    /// handled. The batch-level limits still apply to the requests that are
+    /// kept.
+    pub fn bulk_retrieve_with_policy(
+        &self,
+        params: BulkRetrievePersonParams,
+        policy: BulkValidation,
+    ) -> Result<BulkResult<BulkRetrieveSinglePersonParams, BulkRetrievePersonResponse>, PDLError>
+    {
+        if policy == BulkValidation::RejectBatch {
+            params.validate()?;
+        }
+
+        let (requests, mut result) =
+            BulkResult::partition(params.requests, policy, |request| request.validate())?;
+
+        if !requests.is_empty() {
+            let params = BulkRetrievePersonParams {
+                base_params: params.base_params,
+                requests,
+                additional_params: params.additional_params,
+            };
+            params.validate()?;
+            result.responses = self
+                .client
+                .post::<Vec<BulkRetrievePersonResponse>, BulkRetrievePersonParams>(
+                    PERSON_BULK_RETRIEVE_PATH,
+                    params,
+                )?;
+        }
+
+        Ok(result)
+    }
+
+    /// Applies the client's phone and email normalization to `params`.
+    fn prepare(&self, params: &mut PersonParams) -> Result<(), PDLError> {
+        if let Some(region) = self.client.phone_region() {
+            params.normalize_phones(region)?;
+        }
+        if let Some(hashing) = self.client.email_hashing() {
+            params.hash_emails(hashing)?;
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        client::{PDLCLientOptions, PDLClient},
-        models::common::AdditionalParams,
+        client::{PDLCLientOptions, PDLClient, PDLError, RequestMode},
+        models::{
+            common::{AdditionalParams, BulkValidation, MultiValueEncoding, QueryEncoding},
+            person::{BulkEnrichPersonResponse, PERSON_INPUT_RULES},
+        },
         BaseParams, BulkEnrichPersonParams, BulkEnrichSinglePersonParams, BulkRetrievePersonParams,
-        BulkRetrieveSinglePersonParams, EnrichPersonParams, IdentifyPersonParams, PersonParams,
-        RetrievePersonParams, SearchBaseParams, SearchParams,
+        BulkRetrieveSinglePersonParams, EmailHashing, EnrichPersonParams, IdentifyPersonParams,
+        PersonParams, RetrievePersonParams, SearchBaseParams, SearchParams,
     };
 
-    use super::Person;
+    use super::{Person, PERSON_ENRICH_PATH, PERSON_SEARCH_PATH};
 
     #[test]
     fn test_person_enrich() {
@@ -120,8 +278,10 @@ mod tests {
     #[test]
     fn test_person_enrich_sandbox() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
-        let mut client_options = PDLCLientOptions::default();
-        client_options.sandbox = true;
+        let client_options = PDLCLientOptions {
+            sandbox: true,
+            ..PDLCLientOptions::default()
+        };
         let client = PDLClient::new(&api_key).options(client_options).build();
 
         let person = Person { client };
@@ -214,23 +374,30 @@ mod tests {
         let resp = person.identify(indentify_person_params).expect("ERROR");
 
         assert_eq!(resp.status, 200);
-        assert!(resp.matches.len() >= 1);
+        assert!(!resp.matches.is_empty());
     }
 
     #[test]
     fn test_person_identify_sandbox() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
-        let mut client_options = PDLCLientOptions::default();
-        client_options.sandbox = true;
+        let client_options = PDLCLientOptions {
+            sandbox: true,
+            ..PDLCLientOptions::default()
+        };
         let client = PDLClient::new(&api_key).options(client_options).build();
 
         let person = Person { client };
 
-        let mut base_params = BaseParams::default();
-        base_params.pretty = Some(true);
+        let base_params = BaseParams {
+            pretty: Some(true),
+            ..Default::default()
+        };
 
-        let mut person_params = PersonParams::default();
-        person_params.company = Some(vec!["adams group".to_string()]);
+        let person_params = PersonParams {
+            name: Some(vec!["renee willis".to_string()]),
+            company: Some(vec!["adams group".to_string()]),
+            ..Default::default()
+        };
 
         let indentify_person_params = IdentifyPersonParams {
             base_params: Some(base_params),
@@ -241,7 +408,7 @@ mod tests {
         let resp = person.identify(indentify_person_params).expect("ERROR");
 
         assert_eq!(resp.status, 200);
-        assert!(resp.matches.len() >= 1);
+        assert!(!resp.matches.is_empty());
     }
 
     #[test]
@@ -272,8 +439,10 @@ mod tests {
     #[test]
     fn test_person_search_sandbox() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
-        let mut client_options = PDLCLientOptions::default();
-        client_options.sandbox = true;
+        let client_options = PDLCLientOptions {
+            sandbox: true,
+            ..PDLCLientOptions::default()
+        };
         let client = PDLClient::new(&api_key).options(client_options).build();
 
         let person = Person { client };
@@ -297,7 +466,7 @@ mod tests {
 
         assert_eq!(resp.status, 200);
         assert_eq!(resp.data.unwrap().len(), num_results);
-        assert_eq!(resp.scroll_token.is_some() && !resp.scroll_token.as_ref().unwrap().is_empty(), true);
+        assert!(!resp.scroll_token.unwrap_or_default().is_empty());
     }
 
     #[test]
@@ -324,6 +493,534 @@ mod tests {
         assert_eq!(resp.data.full_name, Some("sean thorne".to_string()));
     }
 
+    #[test]
+    fn test_enrich_person_params_builder() {
+        let params = EnrichPersonParams::builder()
+            .profile("linkedin.com/in/seanthorne")
+            .email("sean@peopledatalabs.com")
+            .email("sthorne@gmail.com")
+            .min_likelihood(6)
+            .pretty(true)
+            .build()
+            .expect("ERROR");
+
+        assert_eq!(
+            params.person_params.profile,
+            Some(vec!["linkedin.com/in/seanthorne".to_string()])
+        );
+        assert_eq!(params.person_params.email.map(|e| e.len()), Some(2));
+        assert_eq!(params.additional_params.unwrap().min_likelihood, Some(6));
+        assert_eq!(params.base_params.unwrap().pretty, Some(true));
+    }
+
+    #[test]
+    fn test_search_params_builder_validates() {
+        assert!(SearchParams::builder().size(10).build().is_err());
+        assert!(SearchParams::builder()
+            .sql("SELECT * FROM person WHERE job_title_role='health';")
+            .query(serde_json::json!({"term": {"job_title_role": "health"}}))
+            .build()
+            .is_err());
+
+        let params = SearchParams::builder()
+            .sql("SELECT * FROM person WHERE job_title_role='health';")
+            .size(10)
+            .build()
+            .expect("ERROR");
+        assert_eq!(params.base_params.unwrap().size, Some(10));
+    }
+
+    #[test]
+    fn test_person_params_minimum_input() {
+        let accepted = [
+            PersonParams::builder().profile("linkedin.com/in/seanthorne"),
+            PersonParams::builder().lid("145991517"),
+            PersonParams::builder()
+                .name("sean thorne")
+                .company("people data labs"),
+            PersonParams::builder()
+                .first_name("sean")
+                .last_name("thorne")
+                .locality("san francisco"),
+            PersonParams::builder()
+                .name("sean thorne")
+                .street_address("455 market st"),
+        ];
+        for builder in accepted {
+            assert!(builder.build().is_ok());
+        }
+
+        let rejected = [
+            PersonParams::builder(),
+            PersonParams::builder().name("sean thorne"),
+            PersonParams::builder()
+                .first_name("sean")
+                .company("people data labs"),
+            PersonParams::builder()
+                .name("sean thorne")
+                .country("united states"),
+            PersonParams::builder().email("  ").school("ucla"),
+        ];
+        for builder in rejected {
+            assert!(builder.build().is_err());
+        }
+    }
+
+    #[test]
+    fn test_person_params_insufficient_input_explains() {
+        let params = PersonParams {
+            first_name: Some(vec!["sean".to_string()]),
+            company: Some(vec!["people data labs".to_string()]),
+            ..PersonParams::default()
+        };
+
+        match params.validate() {
+            Err(PDLError::InsufficientInputError(options)) => {
+                assert_eq!(options.len(), PERSON_INPUT_RULES.len());
+                assert!(options[0].starts_with("first_name + last_name + one of (company"));
+                assert!(options.contains(&"profile".to_string()));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_person_bulk_enrich_validates_before_sending() {
+        let client = PDLClient::new("offline").build();
+        let person = Person { client };
+
+        let invalid = || BulkEnrichSinglePersonParams {
+            params: PersonParams {
+                name: Some(vec!["sean thorne".to_string()]),
+                ..PersonParams::default()
+            },
+            metadata: None,
+        };
+
+        let params = BulkEnrichPersonParams {
+            requires: None,
+            requests: vec![invalid(), invalid()],
+        };
+        assert!(matches!(
+            person.bulk_enrich(params),
+            Err(PDLError::InsufficientInputError(_))
+        ));
+
+        let params = BulkEnrichPersonParams {
+            requires: None,
+            requests: vec![invalid(), invalid()],
+        };
+        let result = person
+            .bulk_enrich_with_policy(params, BulkValidation::DropInvalid)
+            .expect("ERROR");
+        assert!(result.responses.is_empty());
+        assert!(result.sent.is_empty());
+        assert_eq!(
+            result.rejected.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_person_bulk_retrieve_validates_before_sending() {
+        let client = PDLClient::new("offline").build();
+        let person = Person { client };
+
+        let params = BulkRetrievePersonParams::builder()
+            .id("qEnOZ5Oh0poWnQ1luFBfVw_0000")
+            .id("");
+        assert!(params.build().is_err());
+
+        let params = BulkRetrievePersonParams {
+            base_params: None,
+            requests: vec![BulkRetrieveSinglePersonParams {
+                id: String::new(),
+                metadata: None,
+            }],
+            additional_params: None,
+        };
+        let result = person
+            .bulk_retrieve_with_policy(params, BulkValidation::DropInvalid)
+            .expect("ERROR");
+        assert!(result.responses.is_empty());
+        assert_eq!(result.rejected.len(), 1);
+    }
+
+    #[test]
+    fn test_person_request_mode() {
+        let client = PDLClient::new("offline").build();
+
+        let short = EnrichPersonParams::builder()
+            .profile("linkedin.com/in/seanthorne")
+            .build()
+            .expect("ERROR");
+        assert!(client
+            .get_uri_for(PERSON_ENRICH_PATH, &short, Some(&QueryEncoding::default()))
+            .expect("ERROR")
+            .is_some());
+
+        let long = (0..200)
+            .fold(EnrichPersonParams::builder(), |builder, i| {
+                builder.email(format!("person{}@example.com", i))
+            })
+            .build()
+            .expect("ERROR");
+        assert!(client
+            .get_uri_for(PERSON_ENRICH_PATH, &long, Some(&QueryEncoding::default()))
+            .expect("ERROR")
+            .is_none());
+
+        // A query object can't be put in a query string, so it is POSTed.
+        let search = SearchParams::builder()
+            .query(serde_json::json!({"term": {"job_title_role": "health"}}))
+            .build()
+            .expect("ERROR");
+        assert!(client
+            .get_uri_for(PERSON_SEARCH_PATH, &search, None)
+            .expect("ERROR")
+            .is_none());
+
+        let client_options = PDLCLientOptions {
+            request_mode: RequestMode::Get,
+            ..PDLCLientOptions::default()
+        };
+        let client = PDLClient::new("offline").options(client_options).build();
+        assert!(matches!(
+            client.get_uri_for(PERSON_SEARCH_PATH, &search, None),
+            Err(PDLError::SerializationError)
+        ));
+
+        let client_options = PDLCLientOptions {
+            request_mode: RequestMode::Post,
+            ..PDLCLientOptions::default()
+        };
+        let client = PDLClient::new("offline").options(client_options).build();
+        assert!(client
+            .get_uri_for(PERSON_ENRICH_PATH, &short, Some(&QueryEncoding::default()))
+            .expect("ERROR")
+            .is_none());
+    }
+
+    fn multi_valued_params() -> PersonParams {
+        let values = || vec!["washington, dc".to_string(), "smith, jr.".to_string()];
+        PersonParams {
+            pdl_id: Some(values()),
+            name: Some(values()),
+            first_name: Some(values()),
+            last_name: Some(values()),
+            middle_name: Some(values()),
+            location: Some(values()),
+            postal_code: Some(values()),
+            company: Some(values()),
+            school: Some(values()),
+            phone: Some(values()),
+            email: Some(values()),
+            email_hash: Some(values()),
+            profile: Some(values()),
+            lid: Some(values()),
+            birth_date: Some(values()),
+            ..PersonParams::default()
+        }
+    }
+
+    const MULTI_VALUED_FIELDS: [&str; 15] = [
+        "pdl_id",
+        "name",
+        "first_name",
+        "last_name",
+        "middle_name",
+        "location",
+        "postal_code",
+        "company",
+        "school",
+        "phone",
+        "email",
+        "email_hash",
+        "profile",
+        "lid",
+        "birth_date",
+    ];
+
+    #[test]
+    fn test_person_params_repeated_query_encoding() {
+        let params = multi_valued_params();
+        let query = QueryEncoding::default()
+            .to_query_string(&params)
+            .expect("ERROR");
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(&query).expect("ERROR");
+
+        for field in MULTI_VALUED_FIELDS {
+            let values: Vec<&str> = pairs
+                .iter()
+                .filter(|(key, _)| key == field)
+                .map(|(_, value)| value.as_str())
+                .collect();
+            assert_eq!(values, ["washington, dc", "smith, jr."], "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_person_params_json_array_query_encoding() {
+        let params = multi_valued_params();
+        let encoding = QueryEncoding::new(MultiValueEncoding::JsonArray)
+            .field("location", MultiValueEncoding::Repeated);
+        let query = encoding.to_query_string(&params).expect("ERROR");
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(&query).expect("ERROR");
+
+        for field in MULTI_VALUED_FIELDS {
+            let values: Vec<&str> = pairs
+                .iter()
+                .filter(|(key, _)| key == field)
+                .map(|(_, value)| value.as_str())
+                .collect();
+            if field == "location" {
+                assert_eq!(values, ["washington, dc", "smith, jr."]);
+                continue;
+            }
+            assert_eq!(values.len(), 1, "{}", field);
+            let decoded: Vec<String> = serde_json::from_str(values[0]).expect("ERROR");
+            assert_eq!(decoded, ["washington, dc", "smith, jr."], "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_person_params_query_encoding_not_sent() {
+        let params = EnrichPersonParams::builder()
+            .name("sean thorne")
+            .location("washington, dc")
+            .query_encoding(QueryEncoding::new(MultiValueEncoding::Joined))
+            .build()
+            .expect("ERROR");
+        let client = PDLClient::new("offline").build();
+        let uri = client
+            .get_uri_for(
+                PERSON_ENRICH_PATH,
+                &params,
+                Some(&params.person_params.query_encoding),
+            )
+            .expect("ERROR")
+            .expect("ERROR");
+        assert!(uri.contains("location=washington%2C+dc"));
+        assert!(!uri.contains("query_encoding"));
+
+        let body = serde_json::to_value(&params).expect("ERROR");
+        assert_eq!(body["location"], serde_json::json!(["washington, dc"]));
+        assert!(body.get("query_encoding").is_none());
+    }
+
+    #[test]
+    fn test_person_params_hash_emails() {
+        let mut params = PersonParams::builder()
+            .email("test@example.com")
+            .build()
+            .expect("ERROR");
+        params.hash_emails(EmailHashing::default()).expect("ERROR");
+        assert!(params.email.is_none());
+        assert_eq!(
+            params.email_hash,
+            Some(vec![
+                "973dfe463ec85785f5f95af5ba3906eedb2d931c24e69824a89ea65dba4e813b".to_string()
+            ])
+        );
+        params.validate().expect("ERROR");
+
+        let mut params = PersonParams::builder()
+            .email("test@example.com")
+            .email("nope")
+            .build()
+            .expect("ERROR");
+        assert!(params.hash_emails(EmailHashing::default()).is_err());
+        assert_eq!(params.email.as_ref().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn test_person_bulk_enrich_drops_unhashable_emails() {
+        let client_options = PDLCLientOptions {
+            email_hashing: Some(EmailHashing::default()),
+            ..PDLCLientOptions::default()
+        };
+        let person = Person {
+            client: PDLClient::new("offline").options(client_options).build(),
+        };
+
+        let params = BulkEnrichPersonParams::builder()
+            .request(
+                PersonParams::builder()
+                    .email("nope")
+                    .build()
+                    .expect("ERROR"),
+            )
+            .build()
+            .expect("ERROR");
+        let result = person
+            .bulk_enrich_with_policy(params, BulkValidation::DropInvalid)
+            .expect("ERROR");
+        assert!(result.responses.is_empty());
+        assert_eq!(result.rejected.len(), 1);
+    }
+
+    #[test]
+    fn test_person_params_normalize_phones() {
+        let mut params = PersonParams::builder()
+            .phone("(555) 123-4567")
+            .phone("+1 555.123.4567 x3")
+            .phone("+44 20 7946 0958")
+            .build()
+            .expect("ERROR");
+        params.normalize_phones("US").expect("ERROR");
+        assert_eq!(
+            params.phone,
+            Some(vec![
+                "+15551234567".to_string(),
+                "+442079460958".to_string()
+            ])
+        );
+
+        let mut params = PersonParams::builder()
+            .phone("(555) 123-4567")
+            .phone("123")
+            .build()
+            .expect("ERROR");
+        assert!(params.normalize_phones("US").is_err());
+        assert_eq!(
+            params.phone.as_ref().map(|p| p[0].as_str()),
+            Some("(555) 123-4567")
+        );
+    }
+
+    #[test]
+    fn test_person_bulk_enrich_drops_invalid_phones() {
+        let client_options = PDLCLientOptions {
+            phone_region: Some("US".to_string()),
+            ..PDLCLientOptions::default()
+        };
+        let person = Person {
+            client: PDLClient::new("offline").options(client_options).build(),
+        };
+
+        let params = BulkEnrichPersonParams::builder()
+            .request(
+                PersonParams::builder()
+                    .phone("555-1234")
+                    .build()
+                    .expect("ERROR"),
+            )
+            .build()
+            .expect("ERROR");
+        let result = person
+            .bulk_enrich_with_policy(params, BulkValidation::DropInvalid)
+            .expect("ERROR");
+        assert!(result.responses.is_empty());
+        assert_eq!(result.rejected.len(), 1);
+        assert_eq!(
+            result.rejected[0].request.params.phone,
+            Some(vec!["555-1234".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_person_params_canonicalize_profiles() {
+        let mut params = PersonParams::builder()
+            .profile("https://www.linkedin.com/in/seanthorne/")
+            .profile("linkedin.com/in/SeanThorne?trk=x")
+            .profile("https://www.linkedin.com/profile/view?id=145826")
+            .profile("https://twitter.com/seanthorne")
+            .build()
+            .expect("ERROR");
+        params.canonicalize_profiles().expect("ERROR");
+        assert_eq!(
+            params.profile,
+            Some(vec![
+                "linkedin.com/in/seanthorne".to_string(),
+                "twitter.com/seanthorne".to_string()
+            ])
+        );
+        assert_eq!(params.lid, Some(vec!["145826".to_string()]));
+
+        let mut params = PersonParams::builder()
+            .profile("linkedin.com/company/peopledatalabs")
+            .build()
+            .expect("ERROR");
+        assert!(params.canonicalize_profiles().is_err());
+        assert_eq!(
+            params.profile,
+            Some(vec!["linkedin.com/company/peopledatalabs".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_person_params_split_names() {
+        let mut params = PersonParams::builder()
+            .name("Dr. Jane Q. Doe-Smith Jr.")
+            .name("Cher")
+            .company("people data labs")
+            .build()
+            .expect("ERROR");
+        params.split_names().expect("ERROR");
+        assert_eq!(params.first_name, Some(vec!["Jane".to_string()]));
+        assert_eq!(params.middle_name, Some(vec!["Q".to_string()]));
+        assert_eq!(params.last_name, Some(vec!["Doe-Smith".to_string()]));
+        assert_eq!(params.name, Some(vec!["Cher".to_string()]));
+        params.validate().expect("ERROR");
+    }
+
+    #[test]
+    fn test_person_params_fill_address() {
+        let mut params = PersonParams::builder()
+            .name("sean thorne")
+            .company("people data labs")
+            .country("united states")
+            .build()
+            .expect("ERROR");
+        params
+            .fill_address("455 Market St, San Francisco, CA 94105")
+            .expect("ERROR");
+        assert_eq!(params.street_address.as_deref(), Some("455 Market St"));
+        assert_eq!(params.locality.as_deref(), Some("San Francisco"));
+        assert_eq!(params.region.as_deref(), Some("california"));
+        assert_eq!(params.country.as_deref(), Some("united states"));
+        assert_eq!(params.postal_code, Some(vec!["94105".to_string()]));
+        assert_eq!(
+            params.location,
+            Some(vec!["455 Market St, San Francisco, CA 94105".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_bulk_enrich_person_response_keeps_record() {
+        let responses: Vec<BulkEnrichPersonResponse> = serde_json::from_value(serde_json::json!([
+            {
+                "status": 200,
+                "likelihood": 9,
+                "data": {"id": "qEnOZ5Oh0poWnQ1luFBfVw_0000", "full_name": "sean thorne"},
+                "metadata": {"row": "1"}
+            },
+            {"status": 404, "error": {"type": "not_found", "message": "No records were found"}}
+        ]))
+        .expect("ERROR");
+
+        let record = responses[0].data.as_ref().expect("ERROR");
+        assert_eq!(record.full_name.as_deref(), Some("sean thorne"));
+        assert!(responses[1].data.is_none());
+    }
+
+    #[test]
+    fn test_search_params_json_body_omits_unset_fields() {
+        let search = SearchParams::builder()
+            .query(serde_json::json!({"term": {"job_title_role": "health"}}))
+            .size(10)
+            .build()
+            .expect("ERROR");
+
+        assert_eq!(
+            serde_json::to_value(&search).unwrap(),
+            serde_json::json!({
+                "query": {"term": {"job_title_role": "health"}},
+                "size": 10,
+            })
+        );
+    }
+
     #[test]
     fn test_person_bulk_retrive() {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
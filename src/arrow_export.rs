@@ -0,0 +1,704 @@
+//! Arrow and Parquet export of search results, behind the `arrow` feature.
+
+use std::{
+    io::Write,
+    marker::PhantomData,
+    sync::{Arc, OnceLock},
+};
+
+use arrow_array::RecordBatch;
+use arrow_json::ReaderBuilder;
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use reqwest::StatusCode;
+use serde::{
+    de::{
+        self, value::Error as TraceError, DeserializeOwned, DeserializeSeed, IntoDeserializer,
+        MapAccess, SeqAccess, Visitor,
+    },
+    Serialize,
+};
+use serde_json::Value;
+
+use crate::{
+    api::{Company, JobPosting, Person},
+    models::{
+        common::SearchParams, company::CompanyResponse, jobposting::JobPosting as JobPostingRecord,
+        jobposting::JobPostingSearchParams, person::Person as PersonRecord,
+    },
+    PDLError,
+};
+
+/// A record type with a fixed Arrow schema: `Person`, `CompanyResponse`
+/// and `JobPosting` from the search and enrichment responses.
+///
+/// The schema mirrors the record's fields: lists become `List`, nested
+/// objects `Struct` and keyed breakdowns (e.g. `employee_count_by_country`)
+/// `Map<Utf8, _>`. Every column is nullable. Untyped JSON fields such as
+/// `active_job_postings` are stored as `Utf8` JSON text.
+pub trait ArrowRecord: Serialize {
+    fn arrow_schema() -> SchemaRef;
+}
+
+macro_rules! arrow_records {
+    ($($record:ty),*) => {
+        $(
+            impl ArrowRecord for $record {
+                fn arrow_schema() -> SchemaRef {
+                    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+                    SCHEMA.get_or_init(trace_schema::<$record>).clone()
+                }
+            }
+        )*
+    };
+}
+
+arrow_records!(PersonRecord, CompanyResponse, JobPostingRecord);
+
+/// Derives the schema of `T` by walking its `Deserialize` impl.
+fn trace_schema<T: DeserializeOwned>() -> SchemaRef {
+    let mut data_type = DataType::Null;
+    T::deserialize(Tracer {
+        slot: &mut data_type,
+    })
+    .expect("record types can be traced");
+    match data_type {
+        DataType::Struct(fields) => Arc::new(Schema::new(fields)),
+        other => panic!("record types are structs, got {}", other),
+    }
+}
+
+/// A `Deserializer` that records the Arrow type of whatever is deserialized
+/// from it in `slot`, feeding the visitor placeholder values (one element
+/// per list or map) so every nested type is reached.
+struct Tracer<'a> {
+    slot: &'a mut DataType,
+}
+
+macro_rules! trace_primitives {
+    ($($method:ident => $data_type:ident, $visit:ident($($value:expr)?);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+                *self.slot = DataType::$data_type;
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = TraceError;
+
+    trace_primitives! {
+        deserialize_bool => Boolean, visit_bool(false);
+        deserialize_i8 => Int8, visit_i8(0);
+        deserialize_i16 => Int16, visit_i16(0);
+        deserialize_i32 => Int32, visit_i32(0);
+        deserialize_i64 => Int64, visit_i64(0);
+        deserialize_u8 => UInt8, visit_u8(0);
+        deserialize_u16 => UInt16, visit_u16(0);
+        deserialize_u32 => UInt32, visit_u32(0);
+        deserialize_u64 => UInt64, visit_u64(0);
+        deserialize_f32 => Float32, visit_f32(0.0);
+        deserialize_f64 => Float64, visit_f64(0.0);
+        deserialize_char => Utf8, visit_char(' ');
+        deserialize_str => Utf8, visit_str("");
+        deserialize_string => Utf8, visit_str("");
+        deserialize_identifier => Utf8, visit_str("");
+        deserialize_bytes => Binary, visit_bytes(&[]);
+        deserialize_byte_buf => Binary, visit_bytes(&[]);
+        deserialize_unit => Utf8, visit_unit();
+        deserialize_ignored_any => Utf8, visit_unit();
+        // Untyped JSON (`serde_json::Value`), kept as JSON text.
+        deserialize_any => Utf8, visit_unit();
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let mut item = DataType::Null;
+        let value = visitor.visit_seq(TraceSeq {
+            item: Some(&mut item),
+        })?;
+        *self.slot = DataType::new_list(item, true);
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, TraceError> {
+        let mut types = [DataType::Null, DataType::Null];
+        let value = visitor.visit_map(TraceStruct {
+            keys: &[""],
+            types: &mut types,
+            next: 0,
+            trace_keys: true,
+        })?;
+        let [key, value_type] = types;
+        let entries = Fields::from(vec![
+            Field::new("keys", key, false),
+            Field::new("values", value_type, true),
+        ]);
+        *self.slot = DataType::Map(
+            Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+            false,
+        );
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        let mut types = vec![DataType::Null; fields.len()];
+        let value = visitor.visit_map(TraceStruct {
+            keys: fields,
+            types: &mut types,
+            next: 0,
+            trace_keys: false,
+        })?;
+        *self.slot = DataType::Struct(
+            fields
+                .iter()
+                .zip(types)
+                .map(|(name, data_type)| Field::new(*name, data_type, true))
+                .collect(),
+        );
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, TraceError> {
+        *self.slot = DataType::Utf8;
+        let variant = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(variant.into_deserializer())
+    }
+}
+
+/// Yields a single traced element.
+struct TraceSeq<'a> {
+    item: Option<&'a mut DataType>,
+}
+
+impl<'de> SeqAccess<'de> for TraceSeq<'_> {
+    type Error = TraceError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, TraceError> {
+        self.item
+            .take()
+            .map(|slot| seed.deserialize(Tracer { slot }))
+            .transpose()
+    }
+}
+
+/// Yields each struct field, or a single map entry when `trace_keys` is
+/// set, in which case `types` holds the key type followed by the value type.
+struct TraceStruct<'a> {
+    keys: &'static [&'static str],
+    types: &'a mut [DataType],
+    next: usize,
+    trace_keys: bool,
+}
+
+impl<'de> MapAccess<'de> for TraceStruct<'_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, TraceError> {
+        let Some(key) = self.keys.get(self.next) else {
+            return Ok(None);
+        };
+        if self.trace_keys {
+            seed.deserialize(Tracer {
+                slot: &mut self.types[0],
+            })
+            .map(Some)
+        } else {
+            seed.deserialize(key.into_deserializer()).map(Some)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, TraceError> {
+        let index = if self.trace_keys { 1 } else { self.next };
+        self.next += 1;
+        seed.deserialize(Tracer {
+            slot: &mut self.types[index],
+        })
+    }
+}
+
+/// Reshapes a record's JSON to `data_type`: untyped values under `Utf8`
+/// columns become JSON text and fields outside the schema are dropped.
+fn conform(value: Value, data_type: &DataType) -> Value {
+    match (data_type, value) {
+        (_, Value::Null) => Value::Null,
+        (DataType::Utf8, Value::String(text)) => Value::String(text),
+        (DataType::Utf8, other) => Value::String(other.to_string()),
+        (DataType::Struct(fields), Value::Object(mut object)) => Value::Object(
+            fields
+                .iter()
+                .filter_map(|field| {
+                    let value = object.remove(field.name())?;
+                    Some((field.name().clone(), conform(value, field.data_type())))
+                })
+                .collect(),
+        ),
+        (DataType::List(item), Value::Array(items)) => Value::Array(
+            items
+                .into_iter()
+                .map(|value| conform(value, item.data_type()))
+                .collect(),
+        ),
+        (DataType::Map(entries, _), Value::Object(object)) => {
+            let value_type = match entries.data_type() {
+                DataType::Struct(fields) if fields.len() == 2 => fields[1].data_type(),
+                _ => return Value::Object(object),
+            };
+            Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key, conform(value, value_type)))
+                    .collect(),
+            )
+        }
+        (_, other) => other,
+    }
+}
+
+fn export_error(error: impl std::fmt::Display) -> PDLError {
+    PDLError::ExportError(error.to_string())
+}
+
+/// Converts `records` into a `RecordBatch` with `T::arrow_schema()`.
+pub fn to_record_batch<T: ArrowRecord>(records: &[T]) -> Result<RecordBatch, PDLError> {
+    let schema = T::arrow_schema();
+    let data_type = DataType::Struct(schema.fields().clone());
+    let rows = records
+        .iter()
+        .map(|record| {
+            let value = serde_json::to_value(record).map_err(|_| PDLError::SerializationError)?;
+            Ok(conform(value, &data_type))
+        })
+        .collect::<Result<Vec<Value>, PDLError>>()?;
+
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .build_decoder()
+        .map_err(export_error)?;
+    decoder.serialize(&rows).map_err(export_error)?;
+    Ok(decoder
+        .flush()
+        .map_err(export_error)?
+        .unwrap_or_else(|| RecordBatch::new_empty(schema)))
+}
+
+/// Writes batches of records to a Snappy-compressed Parquet file with
+/// `T::arrow_schema()`. Each `write` becomes at least one row group, so
+/// memory use is bounded by the batch size rather than the file size.
+/// Call `finish` to write the footer; a sink dropped without it leaves an
+/// unreadable file.
+pub struct ParquetSink<T: ArrowRecord, W: Write + Send> {
+    writer: ArrowWriter<W>,
+    rows: usize,
+    records: PhantomData<fn(&T)>,
+}
+
+impl<T: ArrowRecord, W: Write + Send> ParquetSink<T, W> {
+    pub fn new(output: W) -> Result<Self, PDLError> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(output, T::arrow_schema(), Some(properties))
+            .map_err(export_error)?;
+        Ok(ParquetSink {
+            writer,
+            rows: 0,
+            records: PhantomData,
+        })
+    }
+
+    pub fn write(&mut self, records: &[T]) -> Result<(), PDLError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let batch = to_record_batch(records)?;
+        self.writer.write(&batch).map_err(export_error)?;
+        self.writer.flush().map_err(export_error)?;
+        self.rows += records.len();
+        Ok(())
+    }
+
+    /// Rows written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Writes the Parquet footer and returns the number of rows written.
+    pub fn finish(self) -> Result<usize, PDLError> {
+        self.writer.close().map_err(export_error)?;
+        Ok(self.rows)
+    }
+}
+
+/// Pages through a search with `next_page`, which is given the scroll token
+/// of the previous page (`None` for the first), writing each page to
+/// `output` as it arrives. Stops when a page is empty or has no scroll
+/// token, or once `max_records` rows are written. A 404 (no matches) ends
+/// the export rather than failing it.
+fn export_pages<T, W, F>(
+    output: W,
+    max_records: Option<usize>,
+    mut next_page: F,
+) -> Result<usize, PDLError>
+where
+    T: ArrowRecord,
+    W: Write + Send,
+    F: FnMut(Option<String>) -> Result<(Vec<T>, Option<String>), PDLError>,
+{
+    let mut sink = ParquetSink::new(output)?;
+    let mut scroll_token = None;
+    loop {
+        let (mut records, next) = match next_page(scroll_token.take()) {
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => (Vec::new(), None),
+            page => page?,
+        };
+        if let Some(max) = max_records {
+            records.truncate(max.saturating_sub(sink.rows()));
+        }
+        sink.write(&records)?;
+
+        let more = !records.is_empty() && max_records.is_none_or(|max| sink.rows() < max);
+        match next.filter(|token| more && !token.is_empty()) {
+            Some(token) => scroll_token = Some(token),
+            None => break,
+        }
+    }
+    sink.finish()
+}
+
+/// `params` for the page after `scroll_token`; the first page uses `params`
+/// as given.
+fn search_page(params: &SearchParams, scroll_token: Option<String>) -> SearchParams {
+    let mut page = params.clone();
+    if scroll_token.is_some() {
+        page.search_base_params.from = None;
+        page.search_base_params.scroll_token = scroll_token;
+    }
+    page
+}
+
+impl Person {
+    /// Runs `search` page by page, following the scroll token, and streams
+    /// the matched records into a Parquet file with the `Person` Arrow
+    /// schema. The page size is `params.base_params.size`; `max_records`
+    /// caps the total. Returns the number of records written.
+    pub fn search_to_parquet<W: Write + Send>(
+        &self,
+        params: SearchParams,
+        output: W,
+        max_records: Option<usize>,
+    ) -> Result<usize, PDLError> {
+        export_pages(output, max_records, |scroll_token| {
+            let response = self.search(search_page(&params, scroll_token))?;
+            Ok((response.data.unwrap_or_default(), response.scroll_token))
+        })
+    }
+}
+
+impl Company {
+    /// Streams company search results into a Parquet file; see
+    /// `Person::search_to_parquet`.
+    pub fn search_to_parquet<W: Write + Send>(
+        &self,
+        params: SearchParams,
+        output: W,
+        max_records: Option<usize>,
+    ) -> Result<usize, PDLError> {
+        export_pages(output, max_records, |scroll_token| {
+            let response = self.search(search_page(&params, scroll_token))?;
+            Ok((response.data, response.scroll_token))
+        })
+    }
+}
+
+impl JobPosting {
+    /// Streams job posting search results into a Parquet file; see
+    /// `Person::search_to_parquet`.
+    pub fn search_to_parquet<W: Write + Send>(
+        &self,
+        params: JobPostingSearchParams,
+        output: W,
+        max_records: Option<usize>,
+    ) -> Result<usize, PDLError> {
+        export_pages(output, max_records, |scroll_token| {
+            let mut page = params.clone();
+            if scroll_token.is_some() {
+                page.search_base_params.scroll_token = scroll_token;
+            }
+            let response = self.search(page)?;
+            Ok((response.data.unwrap_or_default(), response.scroll_token))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use arrow_array::{cast::AsArray, Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    use super::*;
+
+    fn person(value: Value) -> PersonRecord {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn company(value: Value) -> CompanyResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_schemas_use_nested_types() {
+        let person = PersonRecord::arrow_schema();
+        let experience = person.field_with_name("experience").unwrap();
+        let DataType::List(item) = experience.data_type() else {
+            panic!("experience is a list");
+        };
+        let DataType::Struct(fields) = item.data_type() else {
+            panic!("experience items are structs");
+        };
+        let company = fields
+            .iter()
+            .find(|field| field.name() == "company")
+            .unwrap();
+        assert!(matches!(company.data_type(), DataType::Struct(_)));
+        assert_eq!(
+            person.field_with_name("birth_year").unwrap().data_type(),
+            &DataType::Int32
+        );
+        assert!(person
+            .field_with_name("job_company_12mo_employee_growth_rate")
+            .is_ok());
+
+        let company = CompanyResponse::arrow_schema();
+        assert!(matches!(
+            company
+                .field_with_name("employee_count_by_country")
+                .unwrap()
+                .data_type(),
+            DataType::Map(_, false)
+        ));
+        assert_eq!(
+            company
+                .field_with_name("active_job_postings")
+                .unwrap()
+                .data_type(),
+            &DataType::Utf8
+        );
+        assert_eq!(
+            company.field_with_name("type").unwrap().data_type(),
+            &DataType::Utf8
+        );
+
+        let job_posting = JobPostingRecord::arrow_schema();
+        assert_eq!(
+            job_posting
+                .field_with_name("salary_min")
+                .unwrap()
+                .data_type(),
+            &DataType::Float64
+        );
+        assert!(matches!(
+            job_posting
+                .field_with_name("locations")
+                .unwrap()
+                .data_type(),
+            DataType::List(_)
+        ));
+        assert!(job_posting.fields().iter().all(|field| field.is_nullable()));
+    }
+
+    #[test]
+    fn test_record_batch_keeps_nested_values() {
+        let records = vec![
+            person(json!({
+                "id": "abc",
+                "full_name": "jane doe",
+                "birth_year": 1990,
+                "emails": [{"address": "jane@example.com", "type": "professional"}],
+                "experience": [
+                    {"company": {"name": "acme", "founded": 1999}, "is_primary": true},
+                    {"company": null}
+                ],
+            })),
+            person(json!({"id": "def"})),
+        ];
+        let batch = to_record_batch(&records).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), PersonRecord::arrow_schema());
+
+        let ids = batch.column_by_name("id").unwrap().as_string::<i32>();
+        assert_eq!(ids.value(1), "def");
+        let names = batch
+            .column_by_name("full_name")
+            .unwrap()
+            .as_string::<i32>();
+        assert!(names.is_null(1));
+
+        let experience = batch.column_by_name("experience").unwrap().as_list::<i32>();
+        assert_eq!(experience.value_length(0), 2);
+        assert!(experience.is_null(1));
+        let jobs = experience.value(0);
+        let company = jobs
+            .as_struct()
+            .column_by_name("company")
+            .unwrap()
+            .as_struct();
+        let company_names = company.column_by_name("name").unwrap().as_string::<i32>();
+        assert_eq!(company_names.value(0), "acme");
+        assert!(company.is_null(1));
+    }
+
+    #[test]
+    fn test_record_batch_stores_maps_and_untyped_json() {
+        let records = vec![company(json!({
+            "name": "acme",
+            "employee_count_by_country": {"united states": 40, "canada": 2},
+            "active_job_postings": {"total": 3},
+            "top_previous_employers": {"2024-01": [{"id": "x", "count": 2}]},
+        }))];
+        let batch = to_record_batch(&records).unwrap();
+
+        let counts = batch
+            .column_by_name("employee_count_by_country")
+            .unwrap()
+            .as_map();
+        assert_eq!(counts.value_length(0), 2);
+
+        let postings = batch
+            .column_by_name("active_job_postings")
+            .unwrap()
+            .as_string::<i32>();
+        assert_eq!(postings.value(0), r#"{"total":3}"#);
+    }
+
+    #[test]
+    fn test_empty_batch_has_schema() {
+        let batch = to_record_batch::<CompanyResponse>(&[]).unwrap();
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(batch.schema(), CompanyResponse::arrow_schema());
+    }
+
+    #[test]
+    fn test_export_pages_follows_scroll_token_into_parquet() {
+        let path = std::env::temp_dir().join(format!(
+            "peopledatalabs-arrow-{}.parquet",
+            std::process::id()
+        ));
+        let mut tokens = Vec::new();
+        let written = export_pages(File::create(&path).unwrap(), Some(5), |token| {
+            tokens.push(token.clone());
+            let page = tokens.len();
+            let records = (0..2)
+                .map(|i| person(json!({"id": format!("{}-{}", page, i)})))
+                .collect();
+            Ok((records, Some(format!("token-{}", page))))
+        })
+        .unwrap();
+
+        assert_eq!(written, 5);
+        assert_eq!(
+            tokens,
+            vec![
+                None,
+                Some("token-1".to_string()),
+                Some("token-2".to_string())
+            ]
+        );
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 5);
+        assert_eq!(
+            batches[0].schema().fields().len(),
+            PersonRecord::arrow_schema().fields().len()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_pages_ends_on_not_found_or_missing_token() {
+        let written = export_pages(Vec::new(), None, |_| {
+            Err::<(Vec<PersonRecord>, _), _>(PDLError::HTTPError(StatusCode::NOT_FOUND))
+        })
+        .unwrap();
+        assert_eq!(written, 0);
+
+        let mut pages = 0;
+        let written = export_pages(Vec::new(), None, |_| {
+            pages += 1;
+            Ok((vec![company(json!({"name": "acme"}))], None))
+        })
+        .unwrap();
+        assert_eq!((written, pages), (1, 1));
+
+        let result = export_pages(Vec::new(), None, |_| {
+            Err::<(Vec<CompanyResponse>, _), _>(PDLError::HTTPError(StatusCode::UNAUTHORIZED))
+        });
+        assert!(matches!(
+            result,
+            Err(PDLError::HTTPError(StatusCode::UNAUTHORIZED))
+        ));
+    }
+}
@@ -0,0 +1,737 @@
+//! Resumable bulk enrichment jobs over JSONL or CSV input.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "csv")]
+use crate::csv_enrich::{read_params, ColumnMapping, COMPANY_TARGET, PERSON_TARGET};
+use crate::{
+    api::{Company, Person},
+    models::{
+        common::{AdditionalParams, BulkResult, BulkValidation, BULK_MAX_REQUESTS},
+        company::{
+            BulkCompanyEnrichResponse, BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams,
+            CompanyParams,
+        },
+        person::{
+            BulkEnrichPersonParams, BulkEnrichPersonResponse, BulkEnrichSinglePersonParams,
+            PersonParams,
+        },
+    },
+    util::sha256_hex,
+    PDLError,
+};
+
+const OUTPUT_FILE: &str = "output.jsonl";
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobInputFormat {
+    /// One JSON object of input params (`PersonParams` or `CompanyParams`)
+    /// per line. Blank lines are skipped.
+    Jsonl,
+    /// A CSV with a header row, read like `Person::enrich_csv` reads it.
+    /// Needs the `csv` feature.
+    Csv,
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchJobOptions {
+    /// Input format; taken from the input's extension (`.csv` or anything
+    /// else for JSONL) when `None`.
+    pub format: Option<JobInputFormat>,
+    /// CSV column mapping; detected from the header row when `None`.
+    #[cfg(feature = "csv")]
+    pub mapping: Option<ColumnMapping>,
+    #[cfg(feature = "csv")]
+    pub delimiter: u8,
+    /// Rows per bulk request, capped at the API's limit of 100. A job must
+    /// be resumed with the batch size it was started with.
+    pub batch_size: usize,
+    /// Sent as `requires` (people) or `required` (companies).
+    pub required: Option<String>,
+    /// Checked before every batch; setting it (from a Ctrl-C handler, say)
+    /// stops the job after the batch in flight is checkpointed.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Default for BatchJobOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            #[cfg(feature = "csv")]
+            mapping: None,
+            #[cfg(feature = "csv")]
+            delimiter: b',',
+            batch_size: BULK_MAX_REQUESTS,
+            required: None,
+            cancel: None,
+        }
+    }
+}
+
+/// One line of a job's `output.jsonl`, for one input row.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JobOutputRow {
+    /// Position of the row in the input, from 0.
+    pub row: usize,
+    /// Response status; `None` for rows that were not sent.
+    pub status: Option<i32>,
+    pub likelihood: Option<i32>,
+    /// The matched record.
+    pub data: Option<Value>,
+    /// Why the row was not sent.
+    pub error: Option<String>,
+}
+
+/// Progress of a job across every run that worked on it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchJobSummary {
+    pub rows: usize,
+    pub batches: usize,
+    pub completed_batches: usize,
+    /// Batches completed by earlier runs and skipped by this one.
+    pub resumed_batches: usize,
+    /// Rows the API returned a record for.
+    pub matched: usize,
+    /// Rows sent that didn't match.
+    pub unmatched: usize,
+    /// Rows not sent because their input was unreadable or failed
+    /// validation.
+    pub invalid: usize,
+    /// The job stopped because `BatchJobOptions::cancel` was set.
+    pub cancelled: bool,
+}
+
+/// What `checkpoint.json` holds: how far the job got and how long the
+/// output was at that point.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Checkpoint {
+    input_sha256: String,
+    batch_size: usize,
+    completed_batches: usize,
+    output_len: u64,
+    matched: usize,
+    unmatched: usize,
+    invalid: usize,
+}
+
+impl Checkpoint {
+    fn load(path: &Path) -> Result<Option<Self>, PDLError> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|error| job_error(format!("unreadable checkpoint: {}", error))),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(PDLError::IOError(error)),
+        }
+    }
+
+    /// Written to a temporary file and renamed, so a crash never leaves a
+    /// partial checkpoint behind.
+    fn save(&self, path: &Path) -> Result<(), PDLError> {
+        let partial = path.with_extension("json.partial");
+        let text = serde_json::to_string(self).map_err(|_| PDLError::SerializationError)?;
+        let mut file = File::create(&partial).map_err(PDLError::IOError)?;
+        file.write_all(text.as_bytes()).map_err(PDLError::IOError)?;
+        file.sync_all().map_err(PDLError::IOError)?;
+        fs::rename(&partial, path).map_err(PDLError::IOError)
+    }
+}
+
+fn job_error(message: impl Into<String>) -> PDLError {
+    PDLError::JobError(message.into())
+}
+
+/// Params a job reads from its input rows.
+trait JobParams: Default + Serialize + DeserializeOwned {
+    #[cfg(feature = "csv")]
+    fn read_csv(
+        input: &[u8],
+        options: &BatchJobOptions,
+    ) -> Result<Vec<Result<Self, String>>, PDLError>;
+}
+
+impl JobParams for PersonParams {
+    #[cfg(feature = "csv")]
+    fn read_csv(
+        input: &[u8],
+        options: &BatchJobOptions,
+    ) -> Result<Vec<Result<Self, String>>, PDLError> {
+        read_params(
+            input,
+            options.delimiter,
+            options.mapping.as_ref(),
+            &PERSON_TARGET,
+        )
+    }
+}
+
+impl JobParams for CompanyParams {
+    #[cfg(feature = "csv")]
+    fn read_csv(
+        input: &[u8],
+        options: &BatchJobOptions,
+    ) -> Result<Vec<Result<Self, String>>, PDLError> {
+        read_params(
+            input,
+            options.delimiter,
+            options.mapping.as_ref(),
+            &COMPANY_TARGET,
+        )
+    }
+}
+
+/// Reads the input rows as params. A row that can't be read as params holds
+/// the reason instead.
+fn read_rows<P: JobParams>(
+    input: &[u8],
+    format: JobInputFormat,
+    options: &BatchJobOptions,
+) -> Result<Vec<Result<P, String>>, PDLError> {
+    match format {
+        JobInputFormat::Jsonl => Ok(input
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .map(|line| serde_json::from_slice(line).map_err(|error| error.to_string()))
+            .collect()),
+        #[cfg(feature = "csv")]
+        JobInputFormat::Csv => P::read_csv(input, options),
+        #[cfg(not(feature = "csv"))]
+        JobInputFormat::Csv => {
+            let _ = options;
+            Err(job_error("CSV input needs the `csv` feature"))
+        }
+    }
+}
+
+/// Enriches `rows` in batches with `enrich`, appending one `JobOutputRow`
+/// per row to `output.jsonl` in `job_dir` and checkpointing after every
+/// batch. Batches recorded in an existing checkpoint are skipped, and output
+/// written after the checkpoint by an interrupted run is discarded first.
+fn run<P, T, Resp, Build, Enrich, Outcome>(
+    input_sha256: String,
+    rows: Vec<Result<P, String>>,
+    job_dir: &Path,
+    options: &BatchJobOptions,
+    build: Build,
+    mut enrich: Enrich,
+    outcome: Outcome,
+) -> Result<BatchJobSummary, PDLError>
+where
+    Build: Fn(P) -> T,
+    Enrich: FnMut(Vec<T>) -> Result<BulkResult<T, Resp>, PDLError>,
+    Outcome: Fn(usize, &Resp) -> JobOutputRow,
+{
+    fs::create_dir_all(job_dir).map_err(PDLError::IOError)?;
+    let checkpoint_path = job_dir.join(CHECKPOINT_FILE);
+    let batch_size = options.batch_size.clamp(1, BULK_MAX_REQUESTS);
+
+    let mut checkpoint = match Checkpoint::load(&checkpoint_path)? {
+        Some(checkpoint) if checkpoint.input_sha256 != input_sha256 => {
+            return Err(job_error("the input changed since the job was started"));
+        }
+        Some(checkpoint) if checkpoint.batch_size != batch_size => {
+            return Err(job_error(format!(
+                "the job was started with a batch size of {}",
+                checkpoint.batch_size
+            )));
+        }
+        Some(checkpoint) => checkpoint,
+        None => Checkpoint {
+            input_sha256,
+            batch_size,
+            ..Checkpoint::default()
+        },
+    };
+
+    let mut output = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(job_dir.join(OUTPUT_FILE))
+        .map_err(PDLError::IOError)?;
+    output
+        .set_len(checkpoint.output_len)
+        .map_err(PDLError::IOError)?;
+    output.seek(SeekFrom::End(0)).map_err(PDLError::IOError)?;
+
+    let mut summary = BatchJobSummary {
+        rows: rows.len(),
+        batches: rows.len().div_ceil(batch_size),
+        resumed_batches: checkpoint.completed_batches,
+        ..BatchJobSummary::default()
+    };
+
+    let mut rows = rows.into_iter().enumerate();
+    rows.by_ref()
+        .take(checkpoint.completed_batches * batch_size)
+        .for_each(drop);
+    loop {
+        let batch: Vec<(usize, Result<P, String>)> = rows.by_ref().take(batch_size).collect();
+        if batch.is_empty() {
+            break;
+        }
+        if options
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+        {
+            summary.cancelled = true;
+            break;
+        }
+
+        let mut outputs: Vec<Option<JobOutputRow>> = batch.iter().map(|_| None).collect();
+        let mut requests = Vec::with_capacity(batch.len());
+        let mut request_rows = Vec::with_capacity(batch.len());
+        for (position, (row, params)) in batch.into_iter().enumerate() {
+            match params {
+                Ok(params) => {
+                    requests.push(build(params));
+                    request_rows.push(position);
+                }
+                Err(error) => outputs[position] = Some(invalid(row, error)),
+            }
+        }
+
+        let first_row = checkpoint.completed_batches * batch_size;
+        if !requests.is_empty() {
+            let result = enrich(requests)?;
+            for rejected in result.rejected {
+                let position = request_rows[rejected.index];
+                outputs[position] = Some(invalid(first_row + position, rejected.error.to_string()));
+            }
+            for (sent, response) in result.sent.iter().zip(&result.responses) {
+                let position = request_rows[*sent];
+                outputs[position] = Some(outcome(first_row + position, response));
+            }
+        }
+
+        let mut lines = Vec::new();
+        for (position, row_output) in outputs.into_iter().enumerate() {
+            let row_output = row_output
+                .unwrap_or_else(|| invalid(first_row + position, "no response".to_string()));
+            if row_output.data.is_some() {
+                checkpoint.matched += 1;
+            } else if row_output.status.is_some() {
+                checkpoint.unmatched += 1;
+            } else {
+                checkpoint.invalid += 1;
+            }
+            serde_json::to_writer(&mut lines, &row_output)
+                .map_err(|_| PDLError::SerializationError)?;
+            lines.push(b'\n');
+        }
+        output.write_all(&lines).map_err(PDLError::IOError)?;
+        output.sync_data().map_err(PDLError::IOError)?;
+
+        checkpoint.output_len += lines.len() as u64;
+        checkpoint.completed_batches += 1;
+        checkpoint.save(&checkpoint_path)?;
+    }
+
+    summary.completed_batches = checkpoint.completed_batches;
+    summary.matched = checkpoint.matched;
+    summary.unmatched = checkpoint.unmatched;
+    summary.invalid = checkpoint.invalid;
+    Ok(summary)
+}
+
+fn invalid(row: usize, error: String) -> JobOutputRow {
+    JobOutputRow {
+        row,
+        status: None,
+        likelihood: None,
+        data: None,
+        error: Some(error),
+    }
+}
+
+fn response_output<T: Serialize>(
+    row: usize,
+    status: i32,
+    likelihood: Option<i32>,
+    data: Option<&T>,
+) -> JobOutputRow {
+    JobOutputRow {
+        row,
+        status: Some(status),
+        likelihood,
+        data: data.and_then(|data| serde_json::to_value(data).ok()),
+        error: None,
+    }
+}
+
+/// Reads and fingerprints the input, then reads its rows.
+fn read_input<P: JobParams>(
+    input: &Path,
+    options: &BatchJobOptions,
+) -> Result<(String, Vec<Result<P, String>>), PDLError> {
+    let bytes = fs::read(input).map_err(PDLError::IOError)?;
+    let sha256 = sha256_hex(&bytes);
+    let format = options.format.unwrap_or_else(|| {
+        match input.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => JobInputFormat::Csv,
+            _ => JobInputFormat::Jsonl,
+        }
+    });
+    Ok((sha256, read_rows(&bytes, format, options)?))
+}
+
+impl Person {
+    /// Enriches every row of `input` through `bulk_enrich` as a resumable
+    /// job kept in `job_dir`. Results go to `job_dir/output.jsonl`, one
+    /// `JobOutputRow` per input row, and `job_dir/checkpoint.json` records
+    /// the completed batches after each one. Running the job again with the
+    /// same input and `job_dir` skips the completed batches, so a crash or
+    /// cancellation costs at most the batch in flight.
+    pub fn run_bulk_job(
+        &self,
+        input: impl AsRef<Path>,
+        job_dir: impl AsRef<Path>,
+        options: &BatchJobOptions,
+    ) -> Result<BatchJobSummary, PDLError> {
+        let (input_sha256, rows) = read_input::<PersonParams>(input.as_ref(), options)?;
+        run(
+            input_sha256,
+            rows,
+            job_dir.as_ref(),
+            options,
+            |params| BulkEnrichSinglePersonParams {
+                params,
+                metadata: None,
+            },
+            |requests| {
+                self.bulk_enrich_with_policy(
+                    BulkEnrichPersonParams {
+                        requires: options.required.clone(),
+                        requests,
+                    },
+                    BulkValidation::DropInvalid,
+                )
+            },
+            |row, response: &BulkEnrichPersonResponse| {
+                response_output(
+                    row,
+                    response.status,
+                    response.likelihood,
+                    response.data.as_ref(),
+                )
+            },
+        )
+    }
+}
+
+impl Company {
+    /// Enriches every row of `input` as a resumable job kept in `job_dir`;
+    /// see `Person::run_bulk_job`.
+    pub fn run_bulk_job(
+        &self,
+        input: impl AsRef<Path>,
+        job_dir: impl AsRef<Path>,
+        options: &BatchJobOptions,
+    ) -> Result<BatchJobSummary, PDLError> {
+        let (input_sha256, rows) = read_input::<CompanyParams>(input.as_ref(), options)?;
+        let additional_params = options.required.clone().map(|required| AdditionalParams {
+            required: Some(required),
+            ..AdditionalParams::default()
+        });
+        run(
+            input_sha256,
+            rows,
+            job_dir.as_ref(),
+            options,
+            |params| BulkEnrichSingleCompanyParams {
+                params,
+                metadata: None,
+            },
+            |requests| {
+                self.bulk_enrich_with_policy(
+                    BulkEnrichCompanyParams {
+                        base_params: None,
+                        requests,
+                        additional_params: additional_params.clone(),
+                    },
+                    BulkValidation::DropInvalid,
+                )
+            },
+            |row, response: &BulkCompanyEnrichResponse| {
+                // A bulk company response carries at most one record.
+                let data = response.data.as_ref().and_then(|data| data.first());
+                response_output(row, response.status, response.likelihood, data)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::common::RejectedRequest;
+
+    fn job_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pdl-batch-job-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn input_rows(count: usize) -> Vec<Result<PersonParams, String>> {
+        (0..count)
+            .map(|i| {
+                if i == 3 {
+                    return Err("expected value".to_string());
+                }
+                Ok(PersonParams {
+                    email: Some(vec![format!("person{}@example.com", i)]),
+                    ..PersonParams::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Matches every request, failing once `fail_after` batches were sent.
+    fn enrich(
+        sent: &mut Vec<String>,
+        fail_after: Option<usize>,
+        requests: Vec<PersonParams>,
+    ) -> Result<BulkResult<PersonParams, (i32, Value)>, PDLError> {
+        if fail_after.is_some_and(|batches| sent.len() >= batches) {
+            return Err(PDLError::ValidationError);
+        }
+        let emails: Vec<String> = requests
+            .iter()
+            .map(|request| request.email.as_ref().expect("ERROR")[0].clone())
+            .collect();
+        sent.push(emails.join(","));
+        let mut result = BulkResult {
+            responses: Vec::new(),
+            sent: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for (index, email) in emails.into_iter().enumerate() {
+            result.sent.push(index);
+            result.responses.push((200, json!({ "work_email": email })));
+        }
+        Ok(result)
+    }
+
+    fn output(row: usize, &(status, ref data): &(i32, Value)) -> JobOutputRow {
+        JobOutputRow {
+            row,
+            status: Some(status),
+            likelihood: Some(9),
+            data: Some(data.clone()),
+            error: None,
+        }
+    }
+
+    fn read_output(dir: &Path) -> Vec<JobOutputRow> {
+        fs::read_to_string(dir.join(OUTPUT_FILE))
+            .expect("ERROR")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("ERROR"))
+            .collect()
+    }
+
+    fn options(batch_size: usize) -> BatchJobOptions {
+        BatchJobOptions {
+            batch_size,
+            ..BatchJobOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_job_resumes_after_failure_without_resending_batches() {
+        let dir = job_dir("resume");
+        let mut sent = Vec::new();
+        let result = run(
+            "abc".to_string(),
+            input_rows(7),
+            &dir,
+            &options(3),
+            |params| params,
+            |requests| enrich(&mut sent, Some(1), requests),
+            output,
+        );
+        assert!(result.is_err());
+        assert_eq!(read_output(&dir).len(), 3);
+
+        // Output written after the checkpoint is discarded on resume.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(OUTPUT_FILE))
+            .expect("ERROR");
+        file.write_all(b"{\"row\": 3, \"sta").expect("ERROR");
+
+        let mut resent = Vec::new();
+        let summary = run(
+            "abc".to_string(),
+            input_rows(7),
+            &dir,
+            &options(3),
+            |params| params,
+            |requests| enrich(&mut resent, None, requests),
+            output,
+        )
+        .expect("ERROR");
+
+        assert_eq!(
+            resent,
+            vec![
+                "person4@example.com,person5@example.com".to_string(),
+                "person6@example.com".to_string(),
+            ]
+        );
+        assert_eq!(
+            summary,
+            BatchJobSummary {
+                rows: 7,
+                batches: 3,
+                completed_batches: 3,
+                resumed_batches: 1,
+                matched: 6,
+                unmatched: 0,
+                invalid: 1,
+                cancelled: false,
+            }
+        );
+        let rows = read_output(&dir);
+        assert_eq!(
+            rows.iter().map(|row| row.row).collect::<Vec<_>>(),
+            (0..7).collect::<Vec<_>>()
+        );
+        assert_eq!(rows[3].error.as_deref(), Some("expected value"));
+        assert_eq!(
+            rows[5].data,
+            Some(json!({ "work_email": "person5@example.com" }))
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_job_stops_when_cancelled() {
+        let dir = job_dir("cancel");
+        let cancel = Arc::new(AtomicBool::new(false));
+        let options = BatchJobOptions {
+            cancel: Some(cancel.clone()),
+            ..options(2)
+        };
+        let mut sent = Vec::new();
+        let summary = run(
+            "abc".to_string(),
+            input_rows(6),
+            &dir,
+            &options,
+            |params| params,
+            |requests| {
+                cancel.store(true, Ordering::SeqCst);
+                enrich(&mut sent, None, requests)
+            },
+            output,
+        )
+        .expect("ERROR");
+
+        assert!(summary.cancelled);
+        assert_eq!(summary.completed_batches, 1);
+        assert_eq!(read_output(&dir).len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_job_rejects_changed_input_and_batch_size() {
+        let dir = job_dir("mismatch");
+        let mut sent = Vec::new();
+        run(
+            "abc".to_string(),
+            input_rows(2),
+            &dir,
+            &options(1),
+            |params| params,
+            |requests| enrich(&mut sent, None, requests),
+            output,
+        )
+        .expect("ERROR");
+
+        for (sha256, batch_size) in [("def", 1), ("abc", 2)] {
+            let result = run(
+                sha256.to_string(),
+                input_rows(2),
+                &dir,
+                &options(batch_size),
+                |params| params,
+                |requests| enrich(&mut sent, None, requests),
+                output,
+            );
+            assert!(matches!(result, Err(PDLError::JobError(_))));
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejected_requests_are_written_as_invalid() {
+        let dir = job_dir("rejected");
+        let summary = run(
+            "abc".to_string(),
+            vec![Ok(PersonParams::default())],
+            &dir,
+            &options(10),
+            |params| params,
+            |requests: Vec<PersonParams>| {
+                Ok(BulkResult::<PersonParams, (i32, Value)> {
+                    responses: Vec::new(),
+                    sent: Vec::new(),
+                    rejected: requests
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, request)| RejectedRequest {
+                            index,
+                            request,
+                            error: PDLError::ValidationError,
+                        })
+                        .collect(),
+                })
+            },
+            output,
+        )
+        .expect("ERROR");
+
+        assert_eq!(summary.invalid, 1);
+        assert_eq!(
+            read_output(&dir),
+            vec![invalid(0, "Unable to validate.".to_string())]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_jsonl_rows() {
+        let input = b"{\"email\": [\"a@example.com\"]}\n\n{\"email\": 5}\n";
+        let rows: Vec<Result<PersonParams, String>> =
+            read_rows(input, JobInputFormat::Jsonl, &BatchJobOptions::default()).expect("ERROR");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0].as_ref().expect("ERROR").email,
+            Some(vec!["a@example.com".to_string()])
+        );
+        assert!(rows[1].is_err());
+    }
+}
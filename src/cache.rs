@@ -0,0 +1,682 @@
+//! Response cache shared by clones of a `PDLClient`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    util::{sha256_hex, unix_seconds},
+    PDLError,
+};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Endpoints cached with the default TTL: the billed lookups whose response
+/// only depends on their params. A trailing `/` matches any id after it.
+static DEFAULT_CACHED_ENDPOINTS: &[&str] = &[
+    "/person/enrich",
+    "/person/identify",
+    "/person/retrieve/",
+    "/company/enrich",
+    "/company/clean",
+    "/location/clean",
+    "/school/clean",
+    "/job_title/enrich",
+    "/ip/enrich",
+];
+
+/// Hit and miss counts since the cache was created or last cleared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Responses served from the cache, including cached 404s.
+    pub hits: u64,
+    /// Cached 404s returned as `PDLError::HTTPError(404)`.
+    pub not_found_hits: u64,
+    /// Cacheable requests that were sent to the API.
+    pub misses: u64,
+    /// Entries dropped because they expired or, in memory, to stay within
+    /// capacity.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The share of cacheable requests served from the cache, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// A cached response body, or `None` for a cached 404.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    expires_at: u64,
+    body: Option<Value>,
+}
+
+impl Entry {
+    fn is_expired(&self) -> bool {
+        unix_seconds(SystemTime::now()) >= self.expires_at
+    }
+}
+
+/// Least-recently-used entries, dropped beyond `capacity`.
+#[derive(Debug, Default)]
+struct MemoryBackend {
+    capacity: usize,
+    entries: HashMap<String, (Entry, u64)>,
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl MemoryBackend {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.get_mut(key) {
+            self.recency.remove(used);
+            *used = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.get(key)?.0.clone();
+        self.touch(key);
+        Some(entry)
+    }
+
+    /// Stores `entry`, returning how many entries were evicted for it.
+    fn put(&mut self, entry: Entry) -> u64 {
+        let key = entry.key.clone();
+        self.remove(&key);
+        let mut evicted = 0;
+        while self.entries.len() >= self.capacity.max(1) {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+            evicted += 1;
+        }
+        self.entries.insert(key.clone(), (entry, 0));
+        self.touch(&key);
+        evicted
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((_, used)) = self.entries.remove(key) {
+            self.recency.remove(&used);
+        }
+    }
+}
+
+/// One JSON file per entry, named by the SHA-256 of its key.
+#[derive(Debug)]
+struct DiskBackend {
+    dir: PathBuf,
+}
+
+impl DiskBackend {
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", sha256_hex(key.as_bytes())))
+    }
+
+    /// Unreadable or corrupt files are treated as misses.
+    fn get(&self, key: &str) -> Option<Entry> {
+        let text = fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str::<Entry>(&text)
+            .ok()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Written to a temporary file and renamed, so readers never see a
+    /// partial entry.
+    fn put(&self, entry: &Entry) -> Result<(), PDLError> {
+        let path = self.path(&entry.key);
+        let partial = path.with_extension("json.partial");
+        let text = serde_json::to_string(entry).map_err(|_| PDLError::SerializationError)?;
+        fs::write(&partial, text).map_err(PDLError::IOError)?;
+        fs::rename(&partial, &path).map_err(PDLError::IOError)
+    }
+
+    fn remove(&self, key: &str) {
+        // Already gone is fine.
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    fn clear(&self) -> Result<(), PDLError> {
+        for file in fs::read_dir(&self.dir).map_err(PDLError::IOError)? {
+            let path = file.map_err(PDLError::IOError)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                fs::remove_file(path).map_err(PDLError::IOError)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Backend {
+    Memory(MemoryBackend),
+    Disk(DiskBackend),
+}
+
+#[derive(Debug)]
+struct CacheState {
+    backend: Backend,
+    stats: CacheStats,
+}
+
+/// Caches responses of GET-style requests by endpoint and params, so
+/// repeating an identical enrichment, retrieve or cleaner call isn't billed
+/// again. Set it through `PDLCLientOptions.cache`; clones of the client and
+/// of the cache share the same entries.
+///
+/// Only endpoints with a TTL are cached: by default the person and company
+/// enrichment, identify, retrieve, cleaner, job title and IP endpoints, for
+/// a day. 404 responses ("no match") are cached for an hour. Bulk and
+/// search requests aren't cached unless given a TTL with `ttl`.
+///
+/// The key ignores the order of multi-valued params, so
+/// `email=[a, b]` and `email=[b, a]` share an entry.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    state: Arc<Mutex<CacheState>>,
+    default_ttl: Duration,
+    ttls: HashMap<String, Option<Duration>>,
+    negative_ttl: Option<Duration>,
+}
+
+impl ResponseCache {
+    /// An in-process cache holding up to `capacity` responses, dropping the
+    /// least recently used beyond that.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::with_backend(Backend::Memory(MemoryBackend {
+            capacity,
+            ..MemoryBackend::default()
+        }))
+    }
+
+    /// A cache in `dir`, one file per response, kept across process runs.
+    /// The directory is created if needed.
+    pub fn on_disk(dir: impl Into<PathBuf>) -> Result<Self, PDLError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(PDLError::IOError)?;
+        Ok(Self::with_backend(Backend::Disk(DiskBackend { dir })))
+    }
+
+    fn with_backend(backend: Backend) -> Self {
+        ResponseCache {
+            state: Arc::new(Mutex::new(CacheState {
+                backend,
+                stats: CacheStats::default(),
+            })),
+            default_ttl: DEFAULT_TTL,
+            ttls: HashMap::new(),
+            negative_ttl: Some(DEFAULT_NEGATIVE_TTL),
+        }
+    }
+
+    /// The TTL of the endpoints cached by default.
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Caches `endpoint` (e.g. `"/person/search"`) for `ttl`, or stops
+    /// caching it with `None`. An endpoint ending in `/` covers every path
+    /// under it.
+    pub fn ttl(mut self, endpoint: &str, ttl: Option<Duration>) -> Self {
+        self.ttls.insert(endpoint.to_string(), ttl);
+        self
+    }
+
+    /// How long 404s are cached; `None` never caches them.
+    pub fn negative_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    /// Drops every entry and resets the statistics.
+    pub fn clear(&self) -> Result<(), PDLError> {
+        let mut state = self.lock();
+        state.stats = CacheStats::default();
+        match &mut state.backend {
+            Backend::Memory(memory) => {
+                memory.entries.clear();
+                memory.recency.clear();
+                Ok(())
+            }
+            Backend::Disk(disk) => disk.clear(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // A panic while holding the lock can't leave an entry half-written.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn ttl_for(&self, endpoint: &str) -> Option<Duration> {
+        if let Some(ttl) = self.ttls.get(endpoint) {
+            return *ttl;
+        }
+        let prefix = self
+            .ttls
+            .iter()
+            .filter(|(path, _)| path.ends_with('/') && endpoint.starts_with(path.as_str()))
+            .max_by_key(|(path, _)| path.len());
+        if let Some((_, ttl)) = prefix {
+            return *ttl;
+        }
+        DEFAULT_CACHED_ENDPOINTS
+            .iter()
+            .any(|path| *path == endpoint || (path.ends_with('/') && endpoint.starts_with(path)))
+            .then_some(self.default_ttl)
+    }
+
+    /// The cache key of a request to `endpoint` on API `base`, or `None` if
+    /// the endpoint isn't cached.
+    pub(crate) fn key<P: Serialize>(
+        &self,
+        base: &str,
+        endpoint: &str,
+        params: &P,
+    ) -> Option<String> {
+        self.ttl_for(endpoint)?;
+        let params = serde_json::to_value(params).ok()?;
+        Some(format!(
+            "{}{} {}",
+            base,
+            endpoint,
+            canonical_params(params, true)
+        ))
+    }
+
+    /// Returns the cached response for `key`, or sends the request and
+    /// caches its response (or its 404), along with whether the response
+    /// came from the cache.
+    pub(crate) fn fetch<T, F>(
+        &self,
+        endpoint: &str,
+        key: String,
+        send: F,
+    ) -> Result<(T, bool), PDLError>
+    where
+        T: DeserializeOwned,
+        F: FnOnce() -> Result<Value, PDLError>,
+    {
+        if let Some(body) = self.lookup(&key) {
+            return match body {
+                Some(body) => serde_json::from_value(body)
+                    .map(|response| (response, true))
+                    .map_err(|_| PDLError::SerializationError),
+                None => Err(PDLError::HTTPError(StatusCode::NOT_FOUND)),
+            };
+        }
+
+        let result = match send() {
+            Ok(body) => Ok(body),
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => {
+                Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+            }
+            Err(error) => return Err(error),
+        };
+        self.insert(endpoint, key, result.as_ref().ok().cloned())?;
+        serde_json::from_value(result?)
+            .map(|response| (response, false))
+            .map_err(|_| PDLError::SerializationError)
+    }
+
+    /// Caches a response to `endpoint` obtained without `fetch` (one record
+    /// of a bulk response, say), or a 404 when `body` is `None`.
+    pub(crate) fn insert(
+        &self,
+        endpoint: &str,
+        key: String,
+        body: Option<Value>,
+    ) -> Result<(), PDLError> {
+        let ttl = match body {
+            Some(_) => self.ttl_for(endpoint),
+            None => self.negative_ttl,
+        };
+        let Some(ttl) = ttl else {
+            return Ok(());
+        };
+        self.store(Entry {
+            key,
+            expires_at: unix_seconds(SystemTime::now() + ttl),
+            body,
+        })
+    }
+
+    /// The cached body for `key` (`Some(None)` for a cached 404), counting
+    /// the hit or miss and dropping the entry if it has expired.
+    pub(crate) fn lookup(&self, key: &str) -> Option<Option<Value>> {
+        let mut state = self.lock();
+        let entry = match &mut state.backend {
+            Backend::Memory(memory) => memory.get(key),
+            Backend::Disk(disk) => disk.get(key),
+        };
+        let entry = match entry {
+            Some(entry) if entry.is_expired() => {
+                match &mut state.backend {
+                    Backend::Memory(memory) => memory.remove(key),
+                    Backend::Disk(disk) => disk.remove(key),
+                }
+                state.stats.evictions += 1;
+                None
+            }
+            entry => entry,
+        };
+
+        match entry {
+            Some(entry) => {
+                state.stats.hits += 1;
+                if entry.body.is_none() {
+                    state.stats.not_found_hits += 1;
+                }
+                Some(entry.body)
+            }
+            None => {
+                state.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn store(&self, entry: Entry) -> Result<(), PDLError> {
+        let mut state = self.lock();
+        match &mut state.backend {
+            Backend::Memory(memory) => {
+                let evicted = memory.put(entry);
+                state.stats.evictions += evicted;
+                Ok(())
+            }
+            Backend::Disk(disk) => disk.put(&entry),
+        }
+    }
+}
+
+/// `params` as JSON text with keys sorted and the values of multi-valued
+/// params sorted, at the top level and in the `params` of bulk requests.
+/// Other arrays (bulk requests, query clauses) keep their order.
+pub(crate) fn canonical_params(params: Value, sort_arrays: bool) -> Value {
+    match params {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let sort = key == "params";
+                    let value = match value {
+                        Value::Array(items) if sort_arrays => Value::Array(sorted(items)),
+                        value => canonical_params(value, sort),
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| canonical_params(item, false))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Sorts arrays of strings and numbers; arrays holding objects or arrays
+/// are left as they are.
+fn sorted(items: Vec<Value>) -> Vec<Value> {
+    if !items
+        .iter()
+        .all(|item| item.is_string() || item.is_number() || item.is_boolean())
+    {
+        return items
+            .into_iter()
+            .map(|item| canonical_params(item, false))
+            .collect();
+    }
+    let mut items = items;
+    items.sort_by_key(|item| item.to_string());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn send_ok(calls: &Cell<u32>, body: Value) -> impl FnOnce() -> Result<Value, PDLError> + '_ {
+        move || {
+            calls.set(calls.get() + 1);
+            Ok(body)
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_order_of_multi_valued_params() {
+        let cache = ResponseCache::in_memory(10);
+        let a = json!({"email": ["a@x.com", "b@x.com"], "name": ["jane doe"]});
+        let b = json!({"name": ["jane doe"], "email": ["b@x.com", "a@x.com"]});
+        assert_eq!(
+            cache.key("v5", "/person/enrich", &a),
+            cache.key("v5", "/person/enrich", &b)
+        );
+
+        let c = json!({"email": ["a@x.com"], "name": ["jane doe"]});
+        assert_ne!(
+            cache.key("v5", "/person/enrich", &a),
+            cache.key("v5", "/person/enrich", &c)
+        );
+        assert_ne!(
+            cache.key("v5", "/person/enrich", &a),
+            cache.key("v5", "/person/identify", &a)
+        );
+    }
+
+    #[test]
+    fn test_key_keeps_order_of_bulk_requests() {
+        let cache = ResponseCache::in_memory(10).ttl("/person/bulk", Some(DEFAULT_TTL));
+        let bulk = |first: &str, second: &str| {
+            json!({"requests": [
+                {"params": {"email": [first, second]}},
+                {"params": {"email": ["c@x.com"]}},
+            ]})
+        };
+        assert_eq!(
+            cache.key("v5", "/person/bulk", &bulk("a@x.com", "b@x.com")),
+            cache.key("v5", "/person/bulk", &bulk("b@x.com", "a@x.com"))
+        );
+        let swapped = json!({"requests": [
+            {"params": {"email": ["c@x.com"]}},
+            {"params": {"email": ["a@x.com", "b@x.com"]}},
+        ]});
+        assert_ne!(
+            cache.key("v5", "/person/bulk", &bulk("a@x.com", "b@x.com")),
+            cache.key("v5", "/person/bulk", &swapped)
+        );
+    }
+
+    #[test]
+    fn test_only_endpoints_with_ttl_are_cached() {
+        let cache = ResponseCache::in_memory(10)
+            .ttl("/company/enrich", None)
+            .ttl("/person/search", Some(Duration::from_secs(60)));
+        let params = json!({"name": "acme"});
+        assert!(cache.key("v5", "/person/enrich", &params).is_some());
+        assert!(cache.key("v5", "/person/retrieve/abc", &params).is_some());
+        assert!(cache.key("v5", "/person/search", &params).is_some());
+        assert!(cache.key("v5", "/company/enrich", &params).is_none());
+        assert!(cache.key("v5", "/company/search", &params).is_none());
+        assert_eq!(
+            cache.ttl_for("/person/search"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(cache.ttl_for("/person/enrich"), Some(DEFAULT_TTL));
+    }
+
+    #[test]
+    fn test_fetch_serves_repeats_from_cache() {
+        let cache = ResponseCache::in_memory(10);
+        let calls = Cell::new(0);
+        let key = cache.key("v5", "/person/enrich", &json!({"email": ["a@x.com"]}));
+
+        for round in 0..3 {
+            let (body, cached): (Value, bool) = cache
+                .fetch(
+                    "/person/enrich",
+                    key.clone().unwrap(),
+                    send_ok(&calls, json!({"status": 200})),
+                )
+                .unwrap();
+            assert_eq!(body, json!({"status": 200}));
+            assert_eq!(cached, round > 0);
+        }
+        assert_eq!(calls.get(), 1);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fetch_caches_not_found_but_not_other_errors() {
+        let cache = ResponseCache::in_memory(10);
+        let calls = Cell::new(0);
+        let not_found = || {
+            calls.set(calls.get() + 1);
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+        };
+        for _ in 0..2 {
+            let result = cache.fetch::<Value, _>("/person/enrich", "k".to_string(), not_found);
+            assert!(matches!(
+                result,
+                Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+            ));
+        }
+        assert_eq!(calls.get(), 1);
+        assert_eq!(cache.stats().not_found_hits, 1);
+
+        let failing = || {
+            calls.set(calls.get() + 1);
+            Err(PDLError::HTTPError(StatusCode::TOO_MANY_REQUESTS))
+        };
+        for _ in 0..2 {
+            assert!(cache
+                .fetch::<Value, _>("/person/enrich", "other".to_string(), failing)
+                .is_err());
+        }
+        assert_eq!(calls.get(), 3);
+
+        let cache = ResponseCache::in_memory(10).negative_ttl(None);
+        for _ in 0..2 {
+            assert!(cache
+                .fetch::<Value, _>("/person/enrich", "k".to_string(), not_found)
+                .is_err());
+        }
+        assert_eq!(calls.get(), 5);
+    }
+
+    #[test]
+    fn test_memory_cache_evicts_least_recently_used() {
+        let cache = ResponseCache::in_memory(2);
+        let calls = Cell::new(0);
+        let fetch = |key: &str| {
+            cache
+                .fetch::<Value, _>(
+                    "/person/enrich",
+                    key.to_string(),
+                    send_ok(&calls, json!(key)),
+                )
+                .unwrap()
+        };
+        fetch("a");
+        fetch("b");
+        fetch("a");
+        fetch("c"); // evicts "b"
+        assert_eq!(calls.get(), 3);
+        fetch("a");
+        assert_eq!(calls.get(), 3);
+        fetch("b");
+        assert_eq!(calls.get(), 4);
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn test_expired_entries_are_refetched() {
+        let cache = ResponseCache::in_memory(10).default_ttl(Duration::ZERO);
+        let calls = Cell::new(0);
+        for _ in 0..2 {
+            cache
+                .fetch::<Value, _>("/person/enrich", "k".to_string(), send_ok(&calls, json!(1)))
+                .unwrap();
+        }
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_disk_cache_is_shared_across_instances() {
+        let dir = std::env::temp_dir().join(format!("peopledatalabs-cache-{}", std::process::id()));
+        let calls = Cell::new(0);
+        {
+            let cache = ResponseCache::on_disk(&dir).unwrap();
+            let (body, _): (Value, bool) = cache
+                .fetch(
+                    "/company/enrich",
+                    "k".to_string(),
+                    send_ok(&calls, json!({"id": "acme"})),
+                )
+                .unwrap();
+            assert_eq!(body["id"], "acme");
+        }
+        let cache = ResponseCache::on_disk(&dir).unwrap();
+        let (body, _): (Value, bool) = cache
+            .fetch(
+                "/company/enrich",
+                "k".to_string(),
+                send_ok(&calls, json!({"id": "other"})),
+            )
+            .unwrap();
+        assert_eq!(body["id"], "acme");
+        assert_eq!(calls.get(), 1);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.stats(), CacheStats::default());
+        let (body, _): (Value, bool) = cache
+            .fetch(
+                "/company/enrich",
+                "k".to_string(),
+                send_ok(&calls, json!({"id": "other"})),
+            )
+            .unwrap();
+        assert_eq!(body["id"], "other");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -7,12 +7,17 @@ use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 use std::time::Duration;
 
+use crate::{models::common::QueryEncoding, EmailHashing, EnrichmentLedger, ResponseCache};
+
 static APP_USER_AGENT: &str = "PDL-RUST-SDK";
 
 static DEFAULT_API_URL: &str = "https://api.peopledatalabs.com/";
 static DEFAULT_API_VERSION: &str = "v5";
 static DEFAULT_SANDBOX_URL: &str = "https://sandbox.api.peopledatalabs.com/";
 static DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest GET URL sent before `RequestMode::Auto` switches to a POST body.
+/// Kept well under common proxy limits.
+const DEFAULT_MAX_URL_LENGTH: usize = 2048;
 
 #[derive(Debug)]
 pub enum PDLError {
@@ -20,6 +25,19 @@ pub enum PDLError {
     HTTPError(StatusCode),
     SerializationError,
     ValidationError,
+    /// The input cannot match a record. Holds the input combinations that
+    /// would, closest to the given input first.
+    InsufficientInputError(Vec<String>),
+    /// Reading or writing local files (CSV input/output, caches, ...)
+    /// failed.
+    IOError(std::io::Error),
+    /// Converting records to Arrow or writing Parquet failed.
+    ExportError(String),
+    /// Reading or writing the local record store failed.
+    StoreError(String),
+    /// A batch job can't run, e.g. because its checkpoint was written for
+    /// different input.
+    JobError(String),
 }
 
 impl Display for PDLError {
@@ -29,23 +47,70 @@ impl Display for PDLError {
             PDLError::HTTPError(ref s) => write!(f, "Invalid HTTP status code: {}", s),
             PDLError::SerializationError => f.write_str("Unable to serialize."),
             PDLError::ValidationError => f.write_str("Unable to validate."),
+            PDLError::InsufficientInputError(ref options) => write!(
+                f,
+                "Insufficient input to match a record. Provide one of: {}",
+                options.join("; ")
+            ),
+            PDLError::IOError(ref e) => e.fmt(f),
+            PDLError::ExportError(ref message) => write!(f, "Unable to export: {}", message),
+            PDLError::StoreError(ref message) => write!(f, "Record store error: {}", message),
+            PDLError::JobError(ref message) => write!(f, "Batch job error: {}", message),
         }
     }
 }
 
 impl Error for PDLError {}
 
+/// How endpoints that accept both a query string and a JSON body send their
+/// parameters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RequestMode {
+    /// GET with a query string, unless the URL would be longer than
+    /// `max_url_length` or the params can't be query-encoded (e.g. an
+    /// Elasticsearch query object), in which case POST a JSON body.
+    #[default]
+    Auto,
+    /// Always GET with a query string.
+    Get,
+    /// Always POST a JSON body.
+    Post,
+}
+
 /// This is the struct that allows users to pass optional parameters to the PDLClient.
 pub struct PDLCLientOptions {
     pub sandbox: bool,
     pub timeout: Duration,
+    pub request_mode: RequestMode,
+    pub max_url_length: usize,
+    /// When set, person enrichment replaces every `email` with its hash in
+    /// `email_hash` before sending, so plaintext emails never leave the
+    /// process.
+    pub email_hashing: Option<EmailHashing>,
+    /// When set, person enrichment and identify rewrite `phone` values in
+    /// E.164, reading numbers without a country code as national numbers of
+    /// this region (an ISO 3166-1 alpha-2 code such as `"US"`).
+    pub phone_region: Option<String>,
+    /// When set, GET-style responses are cached so identical enrichment,
+    /// retrieve and cleaner calls aren't billed twice. See `ResponseCache`.
+    pub cache: Option<ResponseCache>,
+    /// When set, person and company enrichment skip inputs the ledger has
+    /// seen recently and return the recorded outcome. See
+    /// `EnrichmentLedger`.
+    pub ledger: Option<EnrichmentLedger>,
 }
 
-impl PDLCLientOptions {
-    pub fn default() -> Self {
+impl Default for PDLCLientOptions {
+    fn default() -> Self {
         Self {
             sandbox: false,
             timeout: DEFAULT_TIMEOUT,
+            request_mode: RequestMode::Auto,
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            email_hashing: None,
+            phone_region: None,
+            cache: None,
+            ledger: None,
         }
     }
 }
@@ -58,6 +123,12 @@ pub struct PDLClient {
     base_url: String,
     api_version: String,
     client: rq::Client,
+    request_mode: RequestMode,
+    max_url_length: usize,
+    email_hashing: Option<EmailHashing>,
+    phone_region: Option<String>,
+    cache: Option<ResponseCache>,
+    ledger: Option<EnrichmentLedger>,
 }
 
 /// Builds client based off of API_KEY and Optional Timeout
@@ -76,6 +147,18 @@ fn build_client(api_key: &str, timeout: Option<Duration>) -> rq::Client {
         .expect("Failed to build reqwest client")
 }
 
+/// Encodes `params` as a query string, writing multi-valued params as
+/// `encoding` specifies or, without one, as `serde_urlencoded` does.
+fn query_string<P: Serialize>(
+    params: &P,
+    encoding: Option<&QueryEncoding>,
+) -> Result<String, PDLError> {
+    match encoding {
+        Some(encoding) => encoding.to_query_string(params),
+        None => serde_urlencoded::to_string(params).map_err(|_| PDLError::SerializationError),
+    }
+}
+
 impl PDLClient {
     /// Make a new People Data Labs client with users API Key and API Version.
     pub fn new(api_key: &str) -> Self {
@@ -85,6 +168,12 @@ impl PDLClient {
             base_url: DEFAULT_API_URL.to_string(),
             api_version: DEFAULT_API_VERSION.to_string(),
             client,
+            request_mode: RequestMode::Auto,
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            email_hashing: None,
+            phone_region: None,
+            cache: None,
+            ledger: None,
         }
     }
 
@@ -94,9 +183,16 @@ impl PDLClient {
         self
     }
 
-    /// Adds the ability to update the default timeout or access sandbox mode
-    /// through chaining.
+    /// Adds the ability to update the default timeout, access sandbox mode or
+    /// choose how parameters are sent through chaining.
     pub fn options(mut self, options: PDLCLientOptions) -> Self {
+        self.request_mode = options.request_mode;
+        self.max_url_length = options.max_url_length;
+        self.email_hashing = options.email_hashing;
+        self.phone_region = options.phone_region;
+        self.cache = options.cache;
+        self.ledger = options.ledger;
+
         if options.timeout != DEFAULT_TIMEOUT {
             self.client = build_client(&self.api_key, Some(options.timeout))
         }
@@ -115,9 +211,47 @@ impl PDLClient {
             base_url: self.base_url,
             api_version: self.api_version,
             client: self.client,
+            request_mode: self.request_mode,
+            max_url_length: self.max_url_length,
+            email_hashing: self.email_hashing,
+            phone_region: self.phone_region,
+            cache: self.cache,
+            ledger: self.ledger,
         }
     }
 
+    /// The email hashing applied before person enrichment, if any.
+    pub fn email_hashing(&self) -> Option<EmailHashing> {
+        self.email_hashing
+    }
+
+    /// The default region phones are normalized with before person
+    /// enrichment and identify, if any.
+    pub fn phone_region(&self) -> Option<&str> {
+        self.phone_region.as_deref()
+    }
+
+    /// The response cache, if any, e.g. to read its statistics.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// The enrichment ledger, if any.
+    pub fn ledger(&self) -> Option<&EnrichmentLedger> {
+        self.ledger.as_ref()
+    }
+
+    /// The cache and key for a request to `endpoint`, when it's cached.
+    pub(crate) fn cache_key<P: Serialize>(
+        &self,
+        endpoint: &str,
+        params: &P,
+    ) -> Option<(&ResponseCache, String)> {
+        let cache = self.cache.as_ref()?;
+        let base = format!("{}{}", self.base_url, self.api_version);
+        cache.key(&base, endpoint, params).map(|key| (cache, key))
+    }
+
     /// Sends a GET method through the PeopleDataLabs API. It takes an endpoint &str and params &str.
     /// It returns a generic response or PDLError.
     pub fn get<T, P>(&self, endpoint: &str, params: P) -> Result<T, PDLError>
@@ -125,14 +259,130 @@ impl PDLClient {
         T: DeserializeOwned,
         P: Serialize,
     {
-        let query_params =
-            serde_urlencoded::to_string(params).map_err(|_| PDLError::SerializationError)?;
+        self.get_traced(endpoint, params, None)
+            .map(|(response, _)| response)
+    }
 
-        let uri = format!(
+    /// Like `get`, writing multi-valued params as `encoding` specifies.
+    pub fn get_with_encoding<T, P>(
+        &self,
+        endpoint: &str,
+        params: P,
+        encoding: &QueryEncoding,
+    ) -> Result<T, PDLError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        self.get_traced(endpoint, params, Some(encoding))
+            .map(|(response, _)| response)
+    }
+
+    /// `get` or `get_with_encoding`, also telling whether the response was
+    /// served from the cache.
+    pub(crate) fn get_traced<T, P>(
+        &self,
+        endpoint: &str,
+        params: P,
+        encoding: Option<&QueryEncoding>,
+    ) -> Result<(T, bool), PDLError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        let query_params = query_string(&params, encoding)?;
+        let uri = self.query_uri(endpoint, &query_params);
+
+        if let Some((cache, key)) = self.cache_key(endpoint, &params) {
+            return cache.fetch(endpoint, key, || self.send_get(uri));
+        }
+        self.send_get(uri).map(|response| (response, false))
+    }
+
+    /// Sends params as a GET query string or a JSON POST body depending on
+    /// the client's `RequestMode`. Only for endpoints that accept both.
+    /// Multi-valued params are written as `encoding` specifies, if given.
+    pub fn get_or_post<T, P>(
+        &self,
+        endpoint: &str,
+        params: P,
+        encoding: Option<&QueryEncoding>,
+    ) -> Result<T, PDLError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        self.get_or_post_traced(endpoint, params, encoding)
+            .map(|(response, _)| response)
+    }
+
+    /// `get_or_post`, also telling whether the response was served from the
+    /// cache.
+    pub(crate) fn get_or_post_traced<T, P>(
+        &self,
+        endpoint: &str,
+        params: P,
+        encoding: Option<&QueryEncoding>,
+    ) -> Result<(T, bool), PDLError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        let uri = self.get_uri_for(endpoint, &params, encoding)?;
+
+        if let Some((cache, key)) = self.cache_key(endpoint, &params) {
+            return cache.fetch(endpoint, key, || match uri {
+                Some(uri) => self.send_get(uri),
+                None => self.post(endpoint, &params),
+            });
+        }
+        match uri {
+            Some(uri) => self.send_get(uri),
+            None => self.post(endpoint, params),
+        }
+        .map(|response| (response, false))
+    }
+
+    /// The GET URI `get_or_post` would use for `params`, or `None` when they
+    /// should be POSTed instead.
+    pub(crate) fn get_uri_for<P>(
+        &self,
+        endpoint: &str,
+        params: &P,
+        encoding: Option<&QueryEncoding>,
+    ) -> Result<Option<String>, PDLError>
+    where
+        P: Serialize,
+    {
+        if self.request_mode == RequestMode::Post {
+            return Ok(None);
+        }
+
+        match query_string(params, encoding) {
+            Ok(query_params) => {
+                let uri = self.query_uri(endpoint, &query_params);
+                if self.request_mode == RequestMode::Get || uri.len() <= self.max_url_length {
+                    Ok(Some(uri))
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(_) if self.request_mode == RequestMode::Get => Err(PDLError::SerializationError),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn query_uri(&self, endpoint: &str, query_params: &str) -> String {
+        format!(
             "{}{}{}?{}",
             self.base_url, self.api_version, endpoint, query_params
-        );
+        )
+    }
 
+    fn send_get<T>(&self, uri: String) -> Result<T, PDLError>
+    where
+        T: DeserializeOwned,
+    {
         let resp = self
             .client
             .get(uri)
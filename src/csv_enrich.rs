@@ -0,0 +1,725 @@
+//! CSV-to-bulk-enrichment pipeline, behind the `csv` feature.
+
+use std::io::{Read, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    api::{Company, Person},
+    models::{
+        common::{AdditionalParams, BulkResult, BulkValidation, BULK_MAX_REQUESTS},
+        company::{
+            BulkCompanyEnrichResponse, BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams,
+            CompanyParams,
+        },
+        person::{
+            BulkEnrichPersonParams, BulkEnrichPersonResponse, BulkEnrichSinglePersonParams,
+            PersonParams,
+        },
+    },
+    PDLError,
+};
+
+static DEFAULT_PERSON_FIELDS: &[&str] = &[
+    "id",
+    "full_name",
+    "job_title",
+    "job_company_name",
+    "linkedin_url",
+    "location_name",
+];
+
+static DEFAULT_COMPANY_FIELDS: &[&str] = &["id", "name", "website", "industry", "size"];
+
+/// Header spellings recognized by `ColumnMapping::detect_person`, after
+/// lowercasing and replacing non-alphanumerics with `_`.
+static PERSON_ALIASES: &[(&str, &str)] = &[
+    ("pdl_id", "pdl_id"),
+    ("name", "name"),
+    ("full_name", "name"),
+    ("fullname", "name"),
+    ("first_name", "first_name"),
+    ("firstname", "first_name"),
+    ("first", "first_name"),
+    ("given_name", "first_name"),
+    ("middle_name", "middle_name"),
+    ("last_name", "last_name"),
+    ("lastname", "last_name"),
+    ("last", "last_name"),
+    ("surname", "last_name"),
+    ("family_name", "last_name"),
+    ("email", "email"),
+    ("email_address", "email"),
+    ("e_mail", "email"),
+    ("work_email", "email"),
+    ("personal_email", "email"),
+    ("email_hash", "email_hash"),
+    ("phone", "phone"),
+    ("phone_number", "phone"),
+    ("mobile", "phone"),
+    ("mobile_phone", "phone"),
+    ("profile", "profile"),
+    ("profile_url", "profile"),
+    ("linkedin", "profile"),
+    ("linkedin_url", "profile"),
+    ("twitter_url", "profile"),
+    ("github_url", "profile"),
+    ("lid", "lid"),
+    ("linkedin_id", "lid"),
+    ("company", "company"),
+    ("company_name", "company"),
+    ("employer", "company"),
+    ("organization", "company"),
+    ("school", "school"),
+    ("university", "school"),
+    ("location", "location"),
+    ("address", "location"),
+    ("street_address", "street_address"),
+    ("street", "street_address"),
+    ("city", "locality"),
+    ("locality", "locality"),
+    ("state", "region"),
+    ("province", "region"),
+    ("region", "region"),
+    ("country", "country"),
+    ("zip", "postal_code"),
+    ("zip_code", "postal_code"),
+    ("postal_code", "postal_code"),
+    ("postcode", "postal_code"),
+    ("birth_date", "birth_date"),
+    ("dob", "birth_date"),
+];
+
+/// Header spellings recognized by `ColumnMapping::detect_company`.
+static COMPANY_ALIASES: &[(&str, &str)] = &[
+    ("pdl_id", "pdl_id"),
+    ("name", "name"),
+    ("company", "name"),
+    ("company_name", "name"),
+    ("organization", "name"),
+    ("website", "website"),
+    ("domain", "website"),
+    ("url", "website"),
+    ("company_website", "website"),
+    ("profile", "profile"),
+    ("linkedin", "profile"),
+    ("linkedin_url", "profile"),
+    ("ticker", "ticker"),
+    ("symbol", "ticker"),
+    ("location", "location"),
+    ("address", "location"),
+    ("street_address", "street_address"),
+    ("street", "street_address"),
+    ("city", "locality"),
+    ("locality", "locality"),
+    ("state", "region"),
+    ("province", "region"),
+    ("region", "region"),
+    ("country", "country"),
+    ("zip", "postal_code"),
+    ("zip_code", "postal_code"),
+    ("postal_code", "postal_code"),
+    ("postcode", "postal_code"),
+];
+
+/// Which CSV column feeds which PDL input param (`"email"`, `"company"`,
+/// ...). Several columns may feed the same multi-valued param.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnMapping {
+    /// `(CSV header, param name)` pairs.
+    pub columns: Vec<(String, String)>,
+}
+
+impl ColumnMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the column with header `header` to the param `param`.
+    pub fn column(mut self, header: impl Into<String>, param: impl Into<String>) -> Self {
+        self.columns.push((header.into(), param.into()));
+        self
+    }
+
+    /// Guesses a person mapping from headers such as "Email Address",
+    /// "LinkedIn URL" or "Zip". Unrecognized headers are left out.
+    pub fn detect_person<S: AsRef<str>>(headers: &[S]) -> Self {
+        Self::detect(headers, PERSON_ALIASES)
+    }
+
+    /// Guesses a company mapping from headers such as "Company Name",
+    /// "Domain" or "City".
+    pub fn detect_company<S: AsRef<str>>(headers: &[S]) -> Self {
+        Self::detect(headers, COMPANY_ALIASES)
+    }
+
+    fn detect<S: AsRef<str>>(headers: &[S], aliases: &[(&str, &str)]) -> Self {
+        let columns = headers
+            .iter()
+            .filter_map(|header| {
+                let key = header_key(header.as_ref());
+                aliases
+                    .iter()
+                    .find(|(alias, _)| *alias == key)
+                    .map(|(_, param)| (header.as_ref().to_string(), param.to_string()))
+            })
+            .collect();
+        Self { columns }
+    }
+}
+
+fn header_key(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvEnrichOptions {
+    /// Column mapping; detected from the header row when `None`.
+    pub mapping: Option<ColumnMapping>,
+    /// Record fields written to the output, as dotted paths into the
+    /// response record (`"job_company_name"`, `"experience.0.title"`). Each
+    /// becomes a `pdl_<path>` column. Empty means a default set per target.
+    pub fields: Vec<String>,
+    /// Rows per bulk request, capped at the API's limit of 100.
+    pub batch_size: usize,
+    pub delimiter: u8,
+    /// Sent as `requires` (people) or `required` (companies).
+    pub required: Option<String>,
+}
+
+impl Default for CsvEnrichOptions {
+    fn default() -> Self {
+        Self {
+            mapping: None,
+            fields: Vec::new(),
+            batch_size: BULK_MAX_REQUESTS,
+            delimiter: b',',
+            required: None,
+        }
+    }
+}
+
+/// Row counts from a CSV enrichment run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CsvEnrichSummary {
+    pub rows: usize,
+    /// Rows the API returned a record for.
+    pub matched: usize,
+    /// Rows sent that didn't match.
+    pub unmatched: usize,
+    /// Rows not sent because their input failed validation.
+    pub invalid: usize,
+}
+
+/// The input params and default output fields of one kind of record.
+pub(crate) struct Target {
+    aliases: &'static [(&'static str, &'static str)],
+    /// The params that take a list of values; the rest take one.
+    lists: &'static [&'static str],
+    default_fields: &'static [&'static str],
+}
+
+pub(crate) static PERSON_TARGET: Target = Target {
+    aliases: PERSON_ALIASES,
+    lists: &[
+        "pdl_id",
+        "name",
+        "first_name",
+        "middle_name",
+        "last_name",
+        "location",
+        "postal_code",
+        "company",
+        "school",
+        "phone",
+        "email",
+        "email_hash",
+        "profile",
+        "lid",
+        "birth_date",
+    ],
+    default_fields: DEFAULT_PERSON_FIELDS,
+};
+
+pub(crate) static COMPANY_TARGET: Target = Target {
+    aliases: COMPANY_ALIASES,
+    lists: &["location"],
+    default_fields: DEFAULT_COMPANY_FIELDS,
+};
+
+/// What the output row for one input row holds.
+struct RowOutcome {
+    status: String,
+    likelihood: Option<i32>,
+    record: Option<Value>,
+    error: Option<String>,
+}
+
+fn csv_error(error: csv::Error) -> PDLError {
+    PDLError::IOError(error.into())
+}
+
+/// Resolves `mapping` to the index and param of every mapped column. Every
+/// mapped column must exist and feed a known input param.
+fn mapped_columns<'a>(
+    headers: &[String],
+    mapping: &'a ColumnMapping,
+    target: &Target,
+) -> Result<Vec<(usize, &'a str)>, PDLError> {
+    mapping
+        .columns
+        .iter()
+        .map(|(header, param)| {
+            let known = target.aliases.iter().any(|(_, known)| known == param);
+            headers
+                .iter()
+                .position(|h| h == header)
+                .filter(|_| known)
+                .map(|index| (index, param.as_str()))
+                .ok_or(PDLError::ValidationError)
+        })
+        .collect()
+}
+
+/// Builds params from the non-empty mapped cells of one row. Cells feeding
+/// a list param are collected in column order; for a single-valued param
+/// the last cell wins.
+fn row_params<P: DeserializeOwned>(
+    record: &csv::StringRecord,
+    columns: &[(usize, &str)],
+    target: &Target,
+) -> Result<P, PDLError> {
+    let mut map = Map::new();
+    for (index, param) in columns {
+        let Some(value) = record.get(*index).map(str::trim).filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        let value = Value::String(value.to_string());
+        if target.lists.contains(param) {
+            if let Value::Array(values) = map
+                .entry(*param)
+                .or_insert_with(|| Value::Array(Vec::new()))
+            {
+                values.push(value);
+            }
+        } else {
+            map.insert(param.to_string(), value);
+        }
+    }
+    serde_json::from_value(Value::Object(map)).map_err(|_| PDLError::ValidationError)
+}
+
+/// Reads every row of a CSV into params, with `mapping` or the mapping
+/// detected from the headers. A row whose cells don't fit the params holds
+/// the reason instead.
+pub(crate) fn read_params<P, R>(
+    input: R,
+    delimiter: u8,
+    mapping: Option<&ColumnMapping>,
+    target: &Target,
+) -> Result<Vec<Result<P, String>>, PDLError>
+where
+    P: DeserializeOwned,
+    R: Read,
+{
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(input);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let mapping = mapping
+        .cloned()
+        .unwrap_or_else(|| ColumnMapping::detect(&headers, target.aliases));
+    let columns = mapped_columns(&headers, &mapping, target)?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(csv_error)?;
+            Ok(row_params(&record, &columns, target).map_err(|error| error.to_string()))
+        })
+        .collect()
+}
+
+/// Looks up a dotted path (`experience.0.title`) in a record.
+fn field_value(record: &Value, path: &str) -> String {
+    let value = path.split('.').try_fold(record, |value, key| match value {
+        Value::Array(values) => key.parse::<usize>().ok().and_then(|i| values.get(i)),
+        _ => value.get(key),
+    });
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Reads rows from `input` a batch at a time, builds params with `build`,
+/// enriches them with `enrich` and writes every input row, plus status,
+/// likelihood, error and the selected fields, to `output`.
+fn run<P, R, W, T, Resp, Build, Enrich, Outcome>(
+    input: R,
+    output: W,
+    options: &CsvEnrichOptions,
+    target: &Target,
+    build: Build,
+    mut enrich: Enrich,
+    outcome: Outcome,
+) -> Result<CsvEnrichSummary, PDLError>
+where
+    P: DeserializeOwned,
+    R: Read,
+    W: Write,
+    Build: Fn(P) -> T,
+    Enrich: FnMut(Vec<T>) -> Result<BulkResult<T, Resp>, PDLError>,
+    Outcome: Fn(&Resp) -> RowOutcome,
+{
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(input);
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(output);
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let mapping = options
+        .mapping
+        .clone()
+        .unwrap_or_else(|| ColumnMapping::detect(&headers, target.aliases));
+    let columns = mapped_columns(&headers, &mapping, target)?;
+
+    let fields: Vec<String> = if options.fields.is_empty() {
+        target
+            .default_fields
+            .iter()
+            .map(|field| field.to_string())
+            .collect()
+    } else {
+        options.fields.clone()
+    };
+    let mut output_headers = headers.clone();
+    output_headers.extend(["pdl_status", "pdl_likelihood", "pdl_error"].map(String::from));
+    output_headers.extend(fields.iter().map(|field| format!("pdl_{}", field)));
+    writer.write_record(&output_headers).map_err(csv_error)?;
+
+    let mut summary = CsvEnrichSummary::default();
+    let batch_size = options.batch_size.clamp(1, BULK_MAX_REQUESTS);
+    let mut records = reader.records();
+    loop {
+        let batch: Vec<csv::StringRecord> = records
+            .by_ref()
+            .take(batch_size)
+            .collect::<Result<_, _>>()
+            .map_err(csv_error)?;
+        if batch.is_empty() {
+            break;
+        }
+        summary.rows += batch.len();
+        let mut outcomes: Vec<Option<RowOutcome>> = batch.iter().map(|_| None).collect();
+        let mut requests = Vec::with_capacity(batch.len());
+        let mut request_rows = Vec::with_capacity(batch.len());
+
+        for (row, record) in batch.iter().enumerate() {
+            match row_params::<P>(record, &columns, target) {
+                Ok(params) => {
+                    requests.push(build(params));
+                    request_rows.push(row);
+                }
+                Err(error) => outcomes[row] = Some(invalid(error)),
+            }
+        }
+
+        if !requests.is_empty() {
+            let result = enrich(requests)?;
+            for rejected in result.rejected {
+                outcomes[request_rows[rejected.index]] = Some(invalid(rejected.error));
+            }
+            for (sent, response) in result.sent.iter().zip(&result.responses) {
+                outcomes[request_rows[*sent]] = Some(outcome(response));
+            }
+        }
+
+        for (record, row_outcome) in batch.iter().zip(outcomes) {
+            let row_outcome = row_outcome.unwrap_or_else(|| RowOutcome {
+                status: String::new(),
+                likelihood: None,
+                record: None,
+                error: Some("no response".to_string()),
+            });
+            if row_outcome.record.is_some() {
+                summary.matched += 1;
+            } else if row_outcome.status == "invalid" {
+                summary.invalid += 1;
+            } else {
+                summary.unmatched += 1;
+            }
+
+            let mut row: Vec<String> = record.iter().map(str::to_string).collect();
+            row.resize(headers.len(), String::new());
+            row.push(row_outcome.status);
+            row.push(
+                row_outcome
+                    .likelihood
+                    .map(|likelihood| likelihood.to_string())
+                    .unwrap_or_default(),
+            );
+            row.push(row_outcome.error.unwrap_or_default());
+            row.extend(fields.iter().map(|field| {
+                row_outcome
+                    .record
+                    .as_ref()
+                    .map(|record| field_value(record, field))
+                    .unwrap_or_default()
+            }));
+            writer.write_record(&row).map_err(csv_error)?;
+        }
+        writer.flush().map_err(PDLError::IOError)?;
+    }
+
+    Ok(summary)
+}
+
+fn invalid(error: PDLError) -> RowOutcome {
+    RowOutcome {
+        status: "invalid".to_string(),
+        likelihood: None,
+        record: None,
+        error: Some(error.to_string()),
+    }
+}
+
+fn response_outcome<T: Serialize>(
+    status: i32,
+    likelihood: Option<i32>,
+    data: Option<&T>,
+) -> RowOutcome {
+    RowOutcome {
+        status: status.to_string(),
+        likelihood,
+        record: data.and_then(|data| serde_json::to_value(data).ok()),
+        error: None,
+    }
+}
+
+fn person_outcome(response: &BulkEnrichPersonResponse) -> RowOutcome {
+    response_outcome(response.status, response.likelihood, response.data.as_ref())
+}
+
+fn company_outcome(response: &BulkCompanyEnrichResponse) -> RowOutcome {
+    // A bulk company response carries at most one record.
+    let data = response.data.as_ref().and_then(|data| data.first());
+    response_outcome(response.status, response.likelihood, data)
+}
+
+impl Person {
+    /// Enriches every row of a CSV. Columns are mapped to `PersonParams`
+    /// with `options.mapping` or detected from the headers; rows are sent
+    /// through `bulk_enrich` in batches of up to 100, and each input row is
+    /// written to `output` followed by `pdl_status`, `pdl_likelihood`,
+    /// `pdl_error` and the selected record fields. Rows with insufficient
+    /// input are written with status `invalid` rather than sent.
+    pub fn enrich_csv<R: Read, W: Write>(
+        &self,
+        input: R,
+        output: W,
+        options: &CsvEnrichOptions,
+    ) -> Result<CsvEnrichSummary, PDLError> {
+        run(
+            input,
+            output,
+            options,
+            &PERSON_TARGET,
+            |params: PersonParams| BulkEnrichSinglePersonParams {
+                params,
+                metadata: None,
+            },
+            |requests| {
+                self.bulk_enrich_with_policy(
+                    BulkEnrichPersonParams {
+                        requires: options.required.clone(),
+                        requests,
+                    },
+                    BulkValidation::DropInvalid,
+                )
+            },
+            person_outcome,
+        )
+    }
+}
+
+impl Company {
+    /// Enriches every row of a CSV of companies; see `Person::enrich_csv`.
+    pub fn enrich_csv<R: Read, W: Write>(
+        &self,
+        input: R,
+        output: W,
+        options: &CsvEnrichOptions,
+    ) -> Result<CsvEnrichSummary, PDLError> {
+        let additional_params = options.required.clone().map(|required| AdditionalParams {
+            required: Some(required),
+            ..AdditionalParams::default()
+        });
+        run(
+            input,
+            output,
+            options,
+            &COMPANY_TARGET,
+            |params: CompanyParams| BulkEnrichSingleCompanyParams {
+                params,
+                metadata: None,
+            },
+            |requests| {
+                self.bulk_enrich_with_policy(
+                    BulkEnrichCompanyParams {
+                        base_params: None,
+                        requests,
+                        additional_params: additional_params.clone(),
+                    },
+                    BulkValidation::DropInvalid,
+                )
+            },
+            company_outcome,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::common::RejectedRequest;
+
+    #[test]
+    fn test_detect_person_mapping() {
+        let headers = ["Full Name", "Work Email", "LinkedIn URL", "Zip", "Notes"];
+        assert_eq!(
+            ColumnMapping::detect_person(&headers),
+            ColumnMapping::new()
+                .column("Full Name", "name")
+                .column("Work Email", "email")
+                .column("LinkedIn URL", "profile")
+                .column("Zip", "postal_code")
+        );
+    }
+
+    #[test]
+    fn test_row_params() {
+        let record = csv::StringRecord::from(vec![
+            "a@example.com",
+            " ",
+            "b@example.com",
+            "san francisco",
+            "peopledatalabs.com",
+        ]);
+        let columns = [(0, "email"), (1, "name"), (2, "email"), (3, "locality")];
+        let params: PersonParams = row_params(&record, &columns, &PERSON_TARGET).expect("ERROR");
+        assert_eq!(
+            params.email,
+            Some(vec![
+                "a@example.com".to_string(),
+                "b@example.com".to_string()
+            ])
+        );
+        assert_eq!(params.locality.as_deref(), Some("san francisco"));
+        assert!(params.name.is_none());
+
+        let columns = [(4, "website"), (3, "location")];
+        let params: CompanyParams = row_params(&record, &columns, &COMPANY_TARGET).expect("ERROR");
+        assert_eq!(params.location, Some(vec!["san francisco".to_string()]));
+        assert_eq!(params.website.as_deref(), Some("peopledatalabs.com"));
+    }
+
+    #[test]
+    fn test_csv_pipeline_writes_every_row() {
+        let input = "Name,Email,Company\n\
+                     Sean Thorne,sean@example.com,People Data Labs\n\
+                     Nobody,,\n\
+                     Jane Doe,jane@example.com,Acme\n";
+        let mut output = Vec::new();
+        let options = CsvEnrichOptions {
+            fields: vec!["full_name".to_string(), "experience.0.title".to_string()],
+            ..CsvEnrichOptions::default()
+        };
+
+        let summary = run(
+            input.as_bytes(),
+            &mut output,
+            &options,
+            &PERSON_TARGET,
+            |params: PersonParams| params,
+            |requests: Vec<PersonParams>| {
+                let mut result = BulkResult {
+                    responses: Vec::new(),
+                    sent: Vec::new(),
+                    rejected: Vec::new(),
+                };
+                for (index, request) in requests.into_iter().enumerate() {
+                    match request.validate() {
+                        Ok(()) if index == 0 => {
+                            result.sent.push(index);
+                            result.responses.push((
+                                200,
+                                json!({"full_name": "sean thorne", "experience": [{"title": "ceo"}]}),
+                            ));
+                        }
+                        Ok(()) => {
+                            result.sent.push(index);
+                            result.responses.push((404, Value::Null));
+                        }
+                        Err(error) => result.rejected.push(RejectedRequest {
+                            index,
+                            request,
+                            error,
+                        }),
+                    }
+                }
+                Ok(result)
+            },
+            |(status, record): &(i32, Value)| RowOutcome {
+                status: status.to_string(),
+                likelihood: (*status == 200).then_some(9),
+                record: (!record.is_null()).then(|| record.clone()),
+                error: None,
+            },
+        )
+        .expect("ERROR");
+
+        assert_eq!(
+            summary,
+            CsvEnrichSummary {
+                rows: 3,
+                matched: 1,
+                unmatched: 1,
+                invalid: 1,
+            }
+        );
+        let output = String::from_utf8(output).expect("ERROR");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Name,Email,Company,pdl_status,pdl_likelihood,pdl_error,pdl_full_name,pdl_experience.0.title"
+        );
+        assert_eq!(
+            lines[1],
+            "Sean Thorne,sean@example.com,People Data Labs,200,9,,sean thorne,ceo"
+        );
+        assert!(lines[2].starts_with("Nobody,,,invalid,,\"Insufficient input"));
+        assert_eq!(lines[3], "Jane Doe,jane@example.com,Acme,404,,,,");
+    }
+}
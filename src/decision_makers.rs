@@ -0,0 +1,549 @@
+//! Finding people in given roles and levels at a list of target companies.
+
+use std::collections::HashMap;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::{
+    api::{Company, Person},
+    models::{
+        common::{BaseParams, SearchBaseParams, SearchParams},
+        company::{CompanyParams, CompanyResponse, EnrichCompanyParams},
+        person::{Person as PersonRecord, SearchPersonResponse},
+    },
+    PDLError,
+};
+
+/// Largest page the person search endpoint returns.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// A company to search, by PDL id or by params to enrich it with first.
+#[derive(Clone, Debug)]
+pub enum TargetCompany {
+    Id(String),
+    Params(Box<CompanyParams>),
+}
+
+impl From<&str> for TargetCompany {
+    fn from(id: &str) -> Self {
+        TargetCompany::Id(id.to_string())
+    }
+}
+
+impl From<String> for TargetCompany {
+    fn from(id: String) -> Self {
+        TargetCompany::Id(id)
+    }
+}
+
+impl From<CompanyParams> for TargetCompany {
+    fn from(params: CompanyParams) -> Self {
+        TargetCompany::Params(Box::new(params))
+    }
+}
+
+/// Who to look for, and where. People match when their `job_title_role` is
+/// one of `roles` and their `job_title_levels` include one of `levels`; an
+/// empty list doesn't filter.
+#[derive(Clone, Debug)]
+pub struct DecisionMakerQuery {
+    pub companies: Vec<TargetCompany>,
+    /// `job_title_role` values, e.g. `"engineering"`.
+    pub roles: Vec<String>,
+    /// `job_title_levels` values, e.g. `"vp"` or `"cxo"`.
+    pub levels: Vec<String>,
+    /// Most people kept per company.
+    pub per_company: usize,
+    /// Company ids per search query.
+    pub chunk_size: usize,
+    /// Stop once this many credits were spent, on company enrichments and
+    /// person records together.
+    pub max_credits: Option<usize>,
+}
+
+impl DecisionMakerQuery {
+    pub fn new<T: Into<TargetCompany>>(companies: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            companies: companies.into_iter().map(Into::into).collect(),
+            roles: Vec::new(),
+            levels: Vec::new(),
+            per_company: 5,
+            chunk_size: 100,
+            max_credits: None,
+        }
+    }
+
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.roles.push(role.into());
+        self
+    }
+
+    pub fn level(mut self, level: impl Into<String>) -> Self {
+        self.levels.push(level.into());
+        self
+    }
+
+    pub fn per_company(mut self, per_company: usize) -> Self {
+        self.per_company = per_company;
+        self
+    }
+
+    pub fn max_credits(mut self, max_credits: usize) -> Self {
+        self.max_credits = Some(max_credits);
+        self
+    }
+
+    /// The Elasticsearch query for the people at `company_ids`, leaving out
+    /// the people already found (`found`).
+    fn search_query(&self, company_ids: &[String], found: &[String]) -> Value {
+        let mut must = vec![json!({ "terms": { "job_company_id": company_ids } })];
+        if !self.roles.is_empty() {
+            must.push(json!({ "terms": { "job_title_role": self.roles } }));
+        }
+        if !self.levels.is_empty() {
+            must.push(json!({ "terms": { "job_title_levels": self.levels } }));
+        }
+        let mut query = json!({ "bool": { "must": must } });
+        if !found.is_empty() {
+            query["bool"]["must_not"] = json!([{ "terms": { "id": found } }]);
+        }
+        query
+    }
+}
+
+/// The people found at one company.
+#[derive(Debug)]
+pub struct CompanyDecisionMakers {
+    pub company_id: String,
+    pub people: Vec<PersonRecord>,
+}
+
+#[derive(Debug)]
+pub struct DecisionMakers {
+    /// One entry per distinct company, in the order the targets were given.
+    pub companies: Vec<CompanyDecisionMakers>,
+    /// Targets given as params that company enrichment couldn't match, or
+    /// that weren't enriched because `max_credits` ran out.
+    pub unresolved: Vec<CompanyParams>,
+    /// Company enrichments billed to resolve targets given as params. Misses
+    /// and responses served from the client's cache or ledger are free.
+    pub enrichments: usize,
+    /// Person records the searches returned, each billed a credit,
+    /// including the ones dropped by `per_company`.
+    pub records: usize,
+    /// `max_credits` stopped the search before every company was full.
+    pub truncated: bool,
+}
+
+/// The PDL ids of the targets, from `TargetCompany::Id` as is and from
+/// `TargetCompany::Params` through company enrichment.
+struct ResolvedTargets {
+    company_ids: Vec<String>,
+    unresolved: Vec<CompanyParams>,
+    enrichments: usize,
+    truncated: bool,
+}
+
+/// Resolves `targets` to distinct company ids, enriching params targets
+/// with `enrich`, which also tells whether a response was replayed rather
+/// than billed. Enrichment stops once billed enrichments reach
+/// `max_credits`; the targets left are unresolved.
+fn resolve<F>(
+    targets: &[TargetCompany],
+    max_credits: Option<usize>,
+    mut enrich: F,
+) -> Result<ResolvedTargets, PDLError>
+where
+    F: FnMut(&CompanyParams) -> Result<(CompanyResponse, bool), PDLError>,
+{
+    let mut resolved = ResolvedTargets {
+        company_ids: Vec::new(),
+        unresolved: Vec::new(),
+        enrichments: 0,
+        truncated: false,
+    };
+    for target in targets {
+        let id = match target {
+            TargetCompany::Id(id) => Some(id.clone()),
+            TargetCompany::Params(params) => {
+                if max_credits.is_some_and(|max_credits| resolved.enrichments >= max_credits) {
+                    resolved.truncated = true;
+                    resolved.unresolved.push((**params).clone());
+                    continue;
+                }
+                match enrich(params) {
+                    Ok((response, replayed)) => {
+                        if !replayed {
+                            resolved.enrichments += 1;
+                        }
+                        response.id
+                    }
+                    Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => None,
+                    Err(error) => return Err(error),
+                }
+            }
+        };
+        match id {
+            Some(id) if !resolved.company_ids.contains(&id) => resolved.company_ids.push(id),
+            Some(_) => {}
+            None => {
+                if let TargetCompany::Params(params) = target {
+                    resolved.unresolved.push((**params).clone());
+                }
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Searches `company_ids` a chunk at a time with `search`, until every
+/// company in the chunk has `per_company` people or the results run out.
+/// Pages are followed by scroll token while the same companies are wanted;
+/// once one fills up, a fresh search is started for the rest, leaving out
+/// the people they already have, so no records are bought for full
+/// companies. `spent` credits already count against `query.max_credits`.
+fn find<F>(
+    company_ids: Vec<String>,
+    query: &DecisionMakerQuery,
+    spent: usize,
+    mut search: F,
+) -> Result<DecisionMakers, PDLError>
+where
+    F: FnMut(SearchParams) -> Result<SearchPersonResponse, PDLError>,
+{
+    let mut found: HashMap<String, Vec<PersonRecord>> = company_ids
+        .iter()
+        .map(|id| (id.clone(), Vec::new()))
+        .collect();
+    let mut records = 0;
+    let mut truncated = false;
+    let per_company = query.per_company;
+
+    'chunks: for chunk in company_ids.chunks(query.chunk_size.max(1)) {
+        let mut wanted: Vec<String> = chunk.to_vec();
+        let mut scroll_token = None;
+        loop {
+            let pending: Vec<String> = wanted
+                .iter()
+                .filter(|id| found[*id].len() < per_company)
+                .cloned()
+                .collect();
+            if pending.is_empty() {
+                break;
+            }
+            if pending != wanted {
+                wanted = pending;
+                scroll_token = None;
+            }
+            let size = wanted
+                .iter()
+                .map(|id| per_company - found[id].len())
+                .sum::<usize>()
+                .min(MAX_PAGE_SIZE);
+            let size = match query.max_credits {
+                Some(max_credits) if spent + records >= max_credits => {
+                    truncated = true;
+                    break 'chunks;
+                }
+                Some(max_credits) => size.min(max_credits - spent - records),
+                None => size,
+            };
+            let seen: Vec<String> = wanted
+                .iter()
+                .flat_map(|id| &found[id])
+                .filter_map(|person| person.id.clone())
+                .collect();
+            let params = SearchParams {
+                base_params: Some(BaseParams {
+                    size: Some(size as i32),
+                    ..BaseParams::default()
+                }),
+                search_base_params: SearchBaseParams {
+                    query: Some(query.search_query(&wanted, &seen)),
+                    scroll_token: scroll_token.take(),
+                    ..SearchBaseParams::default()
+                },
+                additional_params: None,
+            };
+            let response = match search(params) {
+                Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => break,
+                response => response?,
+            };
+            let people = response.data.unwrap_or_default();
+            records += people.len();
+            let empty = people.is_empty();
+            for person in people {
+                let company = person
+                    .job_company_id
+                    .as_ref()
+                    .and_then(|id| found.get_mut(id));
+                if let Some(company) = company.filter(|people| people.len() < per_company) {
+                    company.push(person);
+                }
+            }
+            match response
+                .scroll_token
+                .filter(|token| !empty && !token.is_empty())
+            {
+                Some(token) => scroll_token = Some(token),
+                None => break,
+            }
+        }
+    }
+
+    let companies = company_ids
+        .into_iter()
+        .map(|company_id| CompanyDecisionMakers {
+            people: found.remove(&company_id).unwrap_or_default(),
+            company_id,
+        })
+        .collect();
+    Ok(DecisionMakers {
+        companies,
+        unresolved: Vec::new(),
+        enrichments: spent,
+        records,
+        truncated,
+    })
+}
+
+impl Person {
+    /// Finds the people at `query.companies` whose role and level match,
+    /// keeping up to `query.per_company` per company. Targets given as
+    /// `CompanyParams` are enriched first to get their PDL id. Companies are
+    /// searched `query.chunk_size` at a time with a `terms` query on
+    /// `job_company_id`, paging through each chunk until every company in it
+    /// is full or the results run out; a company that fills up is dropped
+    /// from the chunk's query, and `query.max_credits` caps the credits
+    /// spent on company enrichments and person records.
+    pub fn find_decision_makers(
+        &self,
+        query: &DecisionMakerQuery,
+    ) -> Result<DecisionMakers, PDLError> {
+        let company = Company {
+            client: self.client.clone(),
+        };
+        let targets = resolve(&query.companies, query.max_credits, |params| {
+            company.enrich_traced(EnrichCompanyParams {
+                base_params: None,
+                company_params: params.clone(),
+                additional_params: None,
+            })
+        })?;
+
+        let found = find(targets.company_ids, query, targets.enrichments, |params| {
+            self.search(params)
+        })?;
+        Ok(DecisionMakers {
+            unresolved: targets.unresolved,
+            truncated: targets.truncated || found.truncated,
+            ..found
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(people: &[(&str, &str)], scroll_token: Option<&str>) -> SearchPersonResponse {
+        let data: Vec<Value> = people
+            .iter()
+            .map(|(id, company)| json!({ "id": id, "job_company_id": company }))
+            .collect();
+        serde_json::from_value(json!({
+            "status": 200,
+            "data": data,
+            "total": 100,
+            "scroll_token": scroll_token,
+        }))
+        .expect("ERROR")
+    }
+
+    #[test]
+    fn test_search_query_filters_roles_and_levels() {
+        let query = DecisionMakerQuery::new(["a"])
+            .role("engineering")
+            .level("vp");
+        assert_eq!(
+            query.search_query(&["a".to_string(), "b".to_string()], &["p1".to_string()]),
+            json!({"bool": {
+                "must": [
+                    {"terms": {"job_company_id": ["a", "b"]}},
+                    {"terms": {"job_title_role": ["engineering"]}},
+                    {"terms": {"job_title_levels": ["vp"]}},
+                ],
+                "must_not": [{"terms": {"id": ["p1"]}}],
+            }})
+        );
+    }
+
+    /// The company ids, excluded person ids, size and scroll token `params`
+    /// search with.
+    fn sent(params: &SearchParams) -> (Value, Value, Option<i32>, Option<String>) {
+        let query = params.search_base_params.query.as_ref().expect("ERROR");
+        (
+            query["bool"]["must"][0]["terms"]["job_company_id"].clone(),
+            query["bool"]["must_not"][0]["terms"]["id"].clone(),
+            params.base_params.as_ref().and_then(|base| base.size),
+            params.search_base_params.scroll_token.clone(),
+        )
+    }
+
+    fn found(result: &DecisionMakers) -> Vec<(&str, Vec<&str>)> {
+        result
+            .companies
+            .iter()
+            .map(|company| {
+                (
+                    company.company_id.as_str(),
+                    company
+                        .people
+                        .iter()
+                        .filter_map(|person| person.id.as_deref())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_pages_chunks_until_companies_are_full() {
+        let query = DecisionMakerQuery {
+            chunk_size: 2,
+            ..DecisionMakerQuery::new(["a", "b", "c"]).per_company(2)
+        };
+        let mut calls = Vec::new();
+        let result = find(
+            ["a", "b", "c"].map(String::from).to_vec(),
+            &query,
+            0,
+            |params| {
+                calls.push(sent(&params));
+                match calls.len() {
+                    1 => Ok(page(&[("p1", "b"), ("p2", "b")], Some("t1"))),
+                    2 => Ok(page(&[("p3", "a")], Some("t2"))),
+                    3 => Ok(page(&[], Some("t3"))),
+                    _ => Err(PDLError::HTTPError(StatusCode::NOT_FOUND)),
+                }
+            },
+        )
+        .expect("ERROR");
+
+        // Once "b" is full, "a" is searched on its own from the start.
+        assert_eq!(
+            calls,
+            vec![
+                (json!(["a", "b"]), Value::Null, Some(4), None),
+                (json!(["a"]), Value::Null, Some(2), None),
+                (json!(["a"]), json!(["p3"]), Some(1), Some("t2".to_string())),
+                (json!(["c"]), Value::Null, Some(2), None),
+            ]
+        );
+        assert_eq!(
+            found(&result),
+            vec![("a", vec!["p3"]), ("b", vec!["p1", "p2"]), ("c", vec![])]
+        );
+        assert_eq!(result.records, 3);
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_find_skips_people_already_found_and_stops_at_max_credits() {
+        let query = DecisionMakerQuery::new(["a", "b", "c"])
+            .per_company(2)
+            .max_credits(6);
+        let mut calls = Vec::new();
+        let result = find(
+            ["a", "b", "c"].map(String::from).to_vec(),
+            &query,
+            0,
+            |params| {
+                calls.push(sent(&params));
+                match calls.len() {
+                    1 => Ok(page(
+                        &[("p1", "a"), ("p2", "a"), ("p3", "a"), ("p4", "b")],
+                        Some("t1"),
+                    )),
+                    2 => Ok(page(&[("p5", "b")], Some("t2"))),
+                    _ => Ok(page(&[("p6", "c")], Some("t3"))),
+                }
+            },
+        )
+        .expect("ERROR");
+
+        assert_eq!(
+            calls,
+            vec![
+                (json!(["a", "b", "c"]), Value::Null, Some(6), None),
+                (json!(["b", "c"]), json!(["p4"]), Some(2), None),
+                (json!(["c"]), Value::Null, Some(1), None),
+            ]
+        );
+        assert_eq!(
+            found(&result),
+            vec![
+                ("a", vec!["p1", "p2"]),
+                ("b", vec!["p4", "p5"]),
+                ("c", vec!["p6"])
+            ]
+        );
+        assert_eq!(result.records, 6);
+        assert!(result.truncated);
+    }
+
+    fn company(id: Option<&str>) -> CompanyResponse {
+        serde_json::from_value(json!({ "status": 200, "id": id })).expect("ERROR")
+    }
+
+    #[test]
+    fn test_resolve_counts_billed_enrichments_against_max_credits() {
+        let params = |name: &str| {
+            TargetCompany::from(CompanyParams {
+                name: Some(name.to_string()),
+                ..CompanyParams::default()
+            })
+        };
+        let targets = [
+            params("cached"),
+            TargetCompany::from("a"),
+            params("acme"),
+            params("missing"),
+            params("globex"),
+            params("initech"),
+        ];
+        let mut enriched = Vec::new();
+        let resolved = resolve(&targets, Some(2), |params| {
+            let name = params.name.clone().unwrap_or_default();
+            enriched.push(name.clone());
+            match name.as_str() {
+                "cached" => Ok((company(Some("c")), true)),
+                "missing" => Err(PDLError::HTTPError(StatusCode::NOT_FOUND)),
+                name => Ok((company(Some(name)), false)),
+            }
+        })
+        .expect("ERROR");
+
+        assert_eq!(enriched, vec!["cached", "acme", "missing", "globex"]);
+        assert_eq!(resolved.company_ids, vec!["c", "a", "acme", "globex"]);
+        let unresolved: Vec<_> = resolved
+            .unresolved
+            .iter()
+            .filter_map(|params| params.name.as_deref())
+            .collect();
+        assert_eq!(unresolved, vec!["missing", "initech"]);
+        assert_eq!(resolved.enrichments, 2);
+        assert!(resolved.truncated);
+
+        // Nothing is left for person records.
+        let query = DecisionMakerQuery::new(targets).max_credits(2);
+        let result = find(resolved.company_ids, &query, resolved.enrichments, |_| {
+            panic!("over budget")
+        })
+        .expect("ERROR");
+        assert_eq!(result.enrichments, 2);
+        assert_eq!(result.records, 0);
+        assert!(result.truncated);
+    }
+}
@@ -0,0 +1,332 @@
+//! Person-to-company fan-out: the employer records of enriched, searched
+//! or retrieved people.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    api::{company::ENRICH_PATH, Company, Person},
+    models::{
+        common::SearchParams,
+        company::{
+            BulkCompanyEnrichResponse, BulkEnrichCompanyParams, BulkEnrichSingleCompanyParams,
+            CompanyParams, CompanyResponse, EnrichCompanyParams,
+        },
+        person::{
+            EnrichPersonParams, EnrichPersonResponse, Person as PersonRecord, RetrievePersonParams,
+            RetrievePersonResponse, SearchPersonResponse,
+        },
+    },
+    PDLClient, PDLError,
+};
+
+/// A person response along with the company records of the people in it.
+#[derive(Debug)]
+pub struct WithCompanies<R> {
+    pub response: R,
+    /// Company records by PDL id, for every `job_company_id` in `response`
+    /// that company enrichment matched.
+    pub companies: HashMap<String, CompanyResponse>,
+}
+
+impl<R> WithCompanies<R> {
+    /// The record of `person`'s current employer.
+    pub fn company_of(&self, person: &PersonRecord) -> Option<&CompanyResponse> {
+        self.companies.get(person.job_company_id.as_ref()?)
+    }
+}
+
+impl WithCompanies<EnrichPersonResponse> {
+    pub fn company(&self) -> Option<&CompanyResponse> {
+        self.company_of(&self.response.data)
+    }
+}
+
+impl WithCompanies<RetrievePersonResponse> {
+    pub fn company(&self) -> Option<&CompanyResponse> {
+        self.company_of(&self.response.data)
+    }
+}
+
+impl WithCompanies<SearchPersonResponse> {
+    /// Every person found, with their employer's record.
+    pub fn joined(&self) -> Vec<(&PersonRecord, Option<&CompanyResponse>)> {
+        self.response
+            .data
+            .iter()
+            .flatten()
+            .map(|person| (person, self.company_of(person)))
+            .collect()
+    }
+}
+
+/// The distinct `job_company_id`s of `people`, in order of appearance.
+fn company_ids<'a>(people: impl IntoIterator<Item = &'a PersonRecord>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    people
+        .into_iter()
+        .filter_map(|person| person.job_company_id.clone())
+        .filter(|id| seen.insert(id.clone()))
+        .collect()
+}
+
+/// The params a single company enrichment by `id` would send, which is
+/// what the client's cache keys company records by.
+fn enrich_by_id(id: &str) -> EnrichCompanyParams {
+    EnrichCompanyParams {
+        base_params: None,
+        company_params: CompanyParams {
+            pdl_id: Some(id.to_string()),
+            ..CompanyParams::default()
+        },
+        additional_params: None,
+    }
+}
+
+/// What `fetch_companies` found.
+#[derive(Debug, Default)]
+pub(crate) struct FetchedCompanies {
+    pub(crate) companies: HashMap<String, CompanyResponse>,
+    /// Matches bulk enrichment returned, each billed a credit.
+    pub(crate) credits: usize,
+    /// Ids left unsent because of `max_sent`.
+    pub(crate) unsent: Vec<String>,
+}
+
+/// Fetches the companies `ids` name. Ids the client's cache knows are
+/// served from it; the rest, up to `max_sent` of them, go to `bulk_enrich`,
+/// and its matches and 404s are cached as single enrichments by id.
+pub(crate) fn fetch_companies<F>(
+    client: &PDLClient,
+    ids: Vec<String>,
+    max_sent: Option<usize>,
+    bulk_enrich: F,
+) -> Result<FetchedCompanies, PDLError>
+where
+    F: FnOnce(
+        Vec<BulkEnrichSingleCompanyParams>,
+    ) -> Result<Vec<BulkCompanyEnrichResponse>, PDLError>,
+{
+    let mut fetched = FetchedCompanies::default();
+    let mut misses = Vec::new();
+    for id in ids {
+        let cached = client.cache_key(ENRICH_PATH, &enrich_by_id(&id));
+        match cached.as_ref().and_then(|(cache, key)| cache.lookup(key)) {
+            Some(Some(body)) => {
+                let company =
+                    serde_json::from_value(body).map_err(|_| PDLError::SerializationError)?;
+                fetched.companies.insert(id, company);
+            }
+            // A cached 404.
+            Some(None) => {}
+            None if max_sent.is_some_and(|max_sent| misses.len() >= max_sent) => {
+                fetched.unsent.push(id)
+            }
+            None => misses.push((id, cached.map(|(_, key)| key))),
+        }
+    }
+    if misses.is_empty() {
+        return Ok(fetched);
+    }
+
+    let requests = misses
+        .iter()
+        .map(|(id, _)| BulkEnrichSingleCompanyParams {
+            params: enrich_by_id(id).company_params,
+            metadata: None,
+        })
+        .collect();
+    let responses = bulk_enrich(requests)?;
+    for ((id, key), response) in misses.into_iter().zip(responses) {
+        let company = match response.status {
+            200 => response.data.and_then(|data| data.into_iter().next()),
+            _ => None,
+        };
+        if let (Some(cache), Some(key)) = (client.cache(), key) {
+            match (&company, response.status) {
+                (Some(company), _) => {
+                    let body =
+                        serde_json::to_value(company).map_err(|_| PDLError::SerializationError)?;
+                    cache.insert(ENRICH_PATH, key, Some(body))?;
+                }
+                (None, 404) => cache.insert(ENRICH_PATH, key, None)?,
+                (None, _) => {}
+            }
+        }
+        if let Some(company) = company {
+            fetched.credits += 1;
+            fetched.companies.insert(id, company);
+        }
+    }
+    Ok(fetched)
+}
+
+impl Person {
+    /// Enriches the current employers of `people` by their
+    /// `job_company_id`, each distinct company once. Companies are sent to
+    /// company bulk enrichment in batches of up to 100 unless the client's
+    /// cache already holds them, and what bulk enrichment returns is cached
+    /// like `Company::enrich` by `pdl_id`.
+    pub fn companies_of<'a>(
+        &self,
+        people: impl IntoIterator<Item = &'a PersonRecord>,
+    ) -> Result<HashMap<String, CompanyResponse>, PDLError> {
+        let company = Company {
+            client: self.client.clone(),
+        };
+        fetch_companies(&self.client, company_ids(people), None, |requests| {
+            company.bulk_enrich(BulkEnrichCompanyParams {
+                base_params: None,
+                requests,
+                additional_params: None,
+            })
+        })
+        .map(|fetched| fetched.companies)
+    }
+
+    /// `enrich`, followed by `companies_of` the matched person.
+    pub fn enrich_with_companies(
+        &self,
+        params: EnrichPersonParams,
+    ) -> Result<WithCompanies<EnrichPersonResponse>, PDLError> {
+        let response = self.enrich(params)?;
+        let companies = self.companies_of([&response.data])?;
+        Ok(WithCompanies {
+            response,
+            companies,
+        })
+    }
+
+    /// `search`, followed by `companies_of` every person on the page.
+    pub fn search_with_companies(
+        &self,
+        params: SearchParams,
+    ) -> Result<WithCompanies<SearchPersonResponse>, PDLError> {
+        let response = self.search(params)?;
+        let companies = self.companies_of(response.data.iter().flatten())?;
+        Ok(WithCompanies {
+            response,
+            companies,
+        })
+    }
+
+    /// `retrieve`, followed by `companies_of` the retrieved person.
+    pub fn retrieve_with_companies(
+        &self,
+        params: RetrievePersonParams,
+    ) -> Result<WithCompanies<RetrievePersonResponse>, PDLError> {
+        let response = self.retrieve(params)?;
+        let companies = self.companies_of([&response.data])?;
+        Ok(WithCompanies {
+            response,
+            companies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{PDLCLientOptions, ResponseCache};
+
+    fn person(job_company_id: Option<&str>) -> PersonRecord {
+        serde_json::from_value(json!({ "job_company_id": job_company_id })).expect("ERROR")
+    }
+
+    fn bulk_response(id: &str) -> BulkCompanyEnrichResponse {
+        let (status, data) = match id {
+            "missing" => (404, None),
+            _ => (
+                200,
+                Some(vec![json!({ "id": id, "name": format!("company {}", id) })]),
+            ),
+        };
+        serde_json::from_value(json!({ "status": status, "data": data })).expect("ERROR")
+    }
+
+    fn requested_ids(requests: &[BulkEnrichSingleCompanyParams]) -> Vec<String> {
+        requests
+            .iter()
+            .map(|request| request.params.pdl_id.clone().expect("ERROR"))
+            .collect()
+    }
+
+    #[test]
+    fn test_company_ids_are_distinct_and_ordered() {
+        let people = [
+            person(Some("b")),
+            person(None),
+            person(Some("a")),
+            person(Some("b")),
+        ];
+        assert_eq!(company_ids(&people), vec!["b".to_string(), "a".to_string()]);
+    }
+
+    #[test]
+    fn test_fetch_companies_caches_matches_and_not_found() {
+        let cache = ResponseCache::in_memory(100);
+        let client = PDLClient::new("key")
+            .options(PDLCLientOptions {
+                cache: Some(cache.clone()),
+                ..PDLCLientOptions::default()
+            })
+            .build();
+
+        let ids = vec!["a".to_string(), "missing".to_string()];
+        let fetched = fetch_companies(&client, ids.clone(), None, |requests| {
+            assert_eq!(requested_ids(&requests), ids);
+            Ok(requested_ids(&requests)
+                .iter()
+                .map(|id| bulk_response(id))
+                .collect())
+        })
+        .expect("ERROR");
+        assert_eq!(fetched.companies.len(), 1);
+        assert_eq!(fetched.companies["a"].name.as_deref(), Some("company a"));
+        assert_eq!(fetched.credits, 1);
+
+        // Only the unseen company is sent the second time.
+        let ids = ["a", "missing", "c", "d"].map(String::from).to_vec();
+        let fetched = fetch_companies(&client, ids, Some(1), |requests| {
+            assert_eq!(requested_ids(&requests), vec!["c".to_string()]);
+            Ok(vec![bulk_response("c")])
+        })
+        .expect("ERROR");
+        assert_eq!(fetched.companies.len(), 2);
+        assert_eq!(fetched.unsent, vec!["d".to_string()]);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.not_found_hits, 1);
+
+        // A single enrichment by id is served from the same entries.
+        let (cache, key) = client
+            .cache_key(ENRICH_PATH, &enrich_by_id("c"))
+            .expect("ERROR");
+        assert!(matches!(cache.lookup(&key), Some(Some(_))));
+    }
+
+    #[test]
+    fn test_joined_search_results() {
+        let response: SearchPersonResponse = serde_json::from_value(json!({
+            "status": 200,
+            "data": [{"job_company_id": "a"}, {"job_company_id": "z"}, {}],
+            "total": 3,
+        }))
+        .expect("ERROR");
+        let company: CompanyResponse =
+            serde_json::from_value(json!({ "id": "a", "name": "company a" })).expect("ERROR");
+        let result = WithCompanies {
+            response,
+            companies: HashMap::from([("a".to_string(), company)]),
+        };
+
+        let names: Vec<Option<&str>> = result
+            .joined()
+            .into_iter()
+            .map(|(_, company)| company.and_then(|company| company.name.as_deref()))
+            .collect();
+        assert_eq!(names, vec![Some("company a"), None, None]);
+    }
+}
@@ -0,0 +1,405 @@
+//! Flattens nested records (`Person`, `CompanyResponse`, ...) into tables
+//! of string cells for spreadsheets and warehouses.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::PDLError;
+
+/// Name of the table holding one row per record.
+pub const RECORDS_TABLE: &str = "records";
+
+/// How an array inside a record becomes columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArrayHandling {
+    /// The first `n` elements become indexed columns: `emails.0.address`,
+    /// `emails.1.address`. Later elements are dropped.
+    FirstN(usize),
+    /// Values are joined into one column per field with this separator,
+    /// skipping nulls: `experience.title` = `"ceo; founder"`.
+    Joined(String),
+    /// Each element becomes a row of a child table named after the array's
+    /// path, with `_parent` and `_index` columns pointing back at its row.
+    Explode,
+}
+
+impl Default for ArrayHandling {
+    fn default() -> Self {
+        ArrayHandling::Joined("; ".to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FlattenOptions {
+    /// Handling for arrays without an override.
+    pub arrays: ArrayHandling,
+    /// Handling for specific arrays, keyed by their path without indices
+    /// (`"experience"`, `"experience.title.levels"`).
+    pub array_overrides: HashMap<String, ArrayHandling>,
+    /// Field whose value identifies a record in child tables' `_parent`
+    /// column. Records without it use their position instead.
+    pub key_field: String,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            arrays: ArrayHandling::default(),
+            array_overrides: HashMap::new(),
+            key_field: "id".to_string(),
+        }
+    }
+}
+
+impl FlattenOptions {
+    /// Overrides the handling of the array at `path`.
+    pub fn array(mut self, path: impl Into<String>, handling: ArrayHandling) -> Self {
+        self.array_overrides.insert(path.into(), handling);
+        self
+    }
+
+    fn handling(&self, path: &str) -> &ArrayHandling {
+        self.array_overrides
+            .get(&without_indices(path))
+            .unwrap_or(&self.arrays)
+    }
+}
+
+/// Rows of string cells under a fixed list of columns. Missing and null
+/// values are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl FlatTable {
+    /// The cell at `row` under `column`, if both exist and it isn't null.
+    pub fn get(&self, row: usize, column: &str) -> Option<&str> {
+        let index = self.columns.iter().position(|c| c == column)?;
+        self.rows.get(row)?.get(index)?.as_deref()
+    }
+}
+
+type Row = BTreeMap<String, Option<String>>;
+
+/// Flattens `records` into a `RECORDS_TABLE` table, followed by one child
+/// table per exploded array in name order. Columns are the union over all
+/// records, ordered by path with indices compared numerically, so the same
+/// fields always get the same names in the same order.
+pub fn flatten_records<T: Serialize>(
+    records: &[T],
+    options: &FlattenOptions,
+) -> Result<Vec<FlatTable>, PDLError> {
+    let mut tables: BTreeMap<String, Vec<Row>> = BTreeMap::new();
+    let mut rows = Vec::with_capacity(records.len());
+
+    for (position, record) in records.iter().enumerate() {
+        let value = serde_json::to_value(record).map_err(|_| PDLError::SerializationError)?;
+        let key = match value.get(&options.key_field) {
+            Some(Value::String(key)) => key.clone(),
+            Some(key) if !key.is_null() => key.to_string(),
+            _ => position.to_string(),
+        };
+        let mut row = Row::new();
+        walk(&value, "", "", &key, options, &mut row, &mut tables);
+        rows.push(row);
+    }
+
+    let mut flat = vec![table(RECORDS_TABLE, rows)];
+    flat.extend(tables.into_iter().map(|(name, rows)| table(&name, rows)));
+    Ok(flat)
+}
+
+/// Flattens a single record; see `flatten_records`.
+pub fn flatten_record<T: Serialize>(
+    record: &T,
+    options: &FlattenOptions,
+) -> Result<Vec<FlatTable>, PDLError> {
+    flatten_records(std::slice::from_ref(record), options)
+}
+
+fn without_indices(path: &str) -> String {
+    path.split('.')
+        .filter(|segment| segment.parse::<usize>().is_err())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn cell(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Flattens `value` at column `path` into `row`. `base` is the path of the
+/// table's records within the original record, so array overrides and
+/// child table names use full paths.
+fn walk(
+    value: &Value,
+    base: &str,
+    path: &str,
+    key: &str,
+    options: &FlattenOptions,
+    row: &mut Row,
+    tables: &mut BTreeMap<String, Vec<Row>>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (field, value) in map {
+                walk(
+                    value,
+                    base,
+                    &join_path(path, field),
+                    key,
+                    options,
+                    row,
+                    tables,
+                );
+            }
+        }
+        Value::Array(values) => match options.handling(&join_path(base, path)) {
+            ArrayHandling::FirstN(n) => {
+                for (index, value) in values.iter().take(*n).enumerate() {
+                    let path = join_path(path, &index.to_string());
+                    walk(value, base, &path, key, options, row, tables);
+                }
+            }
+            ArrayHandling::Joined(separator) => {
+                // Flatten every element on its own, then join each column.
+                let mut joined: BTreeMap<String, Vec<String>> = BTreeMap::new();
+                for value in values {
+                    let mut element = Row::new();
+                    walk(value, base, path, key, options, &mut element, tables);
+                    for (column, cell) in element {
+                        let cells = joined.entry(column).or_default();
+                        cells.extend(cell);
+                    }
+                }
+                for (column, cells) in joined {
+                    let cell = (!cells.is_empty()).then(|| cells.join(separator));
+                    row.insert(column, cell);
+                }
+            }
+            ArrayHandling::Explode => {
+                let name = without_indices(&join_path(base, path));
+                for (index, value) in values.iter().enumerate() {
+                    let mut child = Row::new();
+                    child.insert("_parent".to_string(), Some(key.to_string()));
+                    child.insert("_index".to_string(), Some(index.to_string()));
+                    let child_key = format!("{}.{}", key, index);
+                    let column = if value.is_object() { "" } else { "value" };
+                    walk(
+                        value, &name, column, &child_key, options, &mut child, tables,
+                    );
+                    tables.entry(name.clone()).or_default().push(child);
+                }
+            }
+        },
+        value => {
+            row.insert(path.to_string(), cell(value));
+        }
+    }
+}
+
+/// Orders column paths segment by segment, numeric segments by value, with
+/// child tables' `_parent` and `_index` first.
+fn compare_columns(a: &str, b: &str) -> Ordering {
+    let rank = |column: &str| match column {
+        "_parent" => 0,
+        "_index" => 1,
+        _ => 2,
+    };
+    rank(a).cmp(&rank(b)).then_with(|| {
+        let mut a = a.split('.');
+        let mut b = b.split('.');
+        loop {
+            match (a.next(), b.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) => {
+                    let order = match (x.parse::<usize>(), y.parse::<usize>()) {
+                        (Ok(x), Ok(y)) => x.cmp(&y),
+                        _ => x.cmp(y),
+                    };
+                    if order != Ordering::Equal {
+                        return order;
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn table(name: &str, rows: Vec<Row>) -> FlatTable {
+    let mut columns: Vec<String> = rows
+        .iter()
+        .flat_map(|row| row.keys().cloned())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    columns.sort_by(|a, b| compare_columns(a, b));
+
+    let rows = rows
+        .into_iter()
+        .map(|mut row| {
+            columns
+                .iter()
+                .map(|column| row.remove(column).flatten())
+                .collect()
+        })
+        .collect();
+    FlatTable {
+        name: name.to_string(),
+        columns,
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::person::Person;
+
+    fn records() -> Vec<Value> {
+        vec![
+            json!({
+                "id": "a",
+                "full_name": "sean thorne",
+                "emails": [
+                    {"address": "sean@example.com", "type": "current_professional"},
+                    {"address": "sean@old.example.com", "type": null}
+                ],
+                "experience": [
+                    {"title": {"name": "ceo", "levels": ["cxo", "owner"]}, "is_primary": true},
+                    {"title": {"name": "founder", "levels": []}, "is_primary": false}
+                ],
+                "interests": ["rust", "data"]
+            }),
+            json!({
+                "full_name": "jane doe",
+                "emails": [],
+                "experience": [
+                    {"title": {"name": "engineer", "levels": ["senior"]}}
+                ],
+                "employee_count_by_country": {"united states": 10}
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_flatten_joined() {
+        let tables = flatten_records(&records(), &FlattenOptions::default()).expect("ERROR");
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.name, RECORDS_TABLE);
+        assert_eq!(
+            table.columns,
+            [
+                "emails.address",
+                "emails.type",
+                "employee_count_by_country.united states",
+                "experience.is_primary",
+                "experience.title.levels",
+                "experience.title.name",
+                "full_name",
+                "id",
+                "interests",
+            ]
+        );
+        assert_eq!(
+            table.get(0, "emails.address"),
+            Some("sean@example.com; sean@old.example.com")
+        );
+        assert_eq!(table.get(0, "emails.type"), Some("current_professional"));
+        assert_eq!(table.get(0, "experience.title.levels"), Some("cxo; owner"));
+        assert_eq!(table.get(0, "experience.is_primary"), Some("true; false"));
+        assert_eq!(table.get(1, "emails.address"), None);
+        assert_eq!(
+            table.get(1, "employee_count_by_country.united states"),
+            Some("10")
+        );
+    }
+
+    #[test]
+    fn test_flatten_first_n_orders_indices_numerically() {
+        let record = json!({"tags": (0..12).map(|i| format!("t{}", i)).collect::<Vec<_>>()});
+        let options = FlattenOptions {
+            arrays: ArrayHandling::FirstN(11),
+            ..FlattenOptions::default()
+        };
+        let tables = flatten_record(&record, &options).expect("ERROR");
+        let columns: Vec<String> = (0..11).map(|i| format!("tags.{}", i)).collect();
+        assert_eq!(tables[0].columns, columns);
+        assert_eq!(tables[0].get(0, "tags.10"), Some("t10"));
+    }
+
+    #[test]
+    fn test_flatten_explode() {
+        let options = FlattenOptions::default()
+            .array("experience", ArrayHandling::Explode)
+            .array("experience.title.levels", ArrayHandling::Explode)
+            .array("emails", ArrayHandling::FirstN(1));
+        let tables = flatten_records(&records(), &options).expect("ERROR");
+        let names: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [RECORDS_TABLE, "experience", "experience.title.levels"]
+        );
+
+        let records = &tables[0];
+        assert!(!records.columns.iter().any(|c| c.starts_with("experience")));
+        assert_eq!(records.get(0, "emails.0.address"), Some("sean@example.com"));
+        assert!(!records.columns.contains(&"emails.1.address".to_string()));
+
+        let experience = &tables[1];
+        assert_eq!(
+            experience.columns,
+            ["_parent", "_index", "is_primary", "title.name"]
+        );
+        assert_eq!(experience.rows.len(), 3);
+        assert_eq!(experience.get(1, "_parent"), Some("a"));
+        assert_eq!(experience.get(1, "title.name"), Some("founder"));
+        // The second record has no id, so its position is the key.
+        assert_eq!(experience.get(2, "_parent"), Some("1"));
+
+        let levels = &tables[2];
+        assert_eq!(levels.columns, ["_parent", "_index", "value"]);
+        assert_eq!(levels.get(1, "_parent"), Some("a.0"));
+        assert_eq!(levels.get(1, "value"), Some("owner"));
+        assert_eq!(levels.get(2, "_parent"), Some("1.0"));
+    }
+
+    #[test]
+    fn test_flatten_person_record() {
+        let person: Person = serde_json::from_value(json!({
+            "id": "qEnOZ5Oh0poWnQ1luFBfVw_0000",
+            "full_name": "sean thorne",
+            "experience": [{"title": {"name": "co-founder and chief executive officer"}}],
+        }))
+        .expect("ERROR");
+        let tables = flatten_record(&person, &FlattenOptions::default()).expect("ERROR");
+        assert_eq!(tables[0].get(0, "full_name"), Some("sean thorne"));
+        assert_eq!(
+            tables[0].get(0, "experience.title.name"),
+            Some("co-founder and chief executive officer")
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Corporate trees built from the parent, subsidiary and affiliate ids of
+//! company records.
+
+use std::collections::HashSet;
+
+use reqwest::StatusCode;
+
+use crate::{
+    api::Company,
+    fanout::{fetch_companies, FetchedCompanies},
+    models::company::{BulkEnrichCompanyParams, CompanyResponse},
+    PDLError,
+};
+
+#[derive(Clone, Debug)]
+pub struct HierarchyOptions {
+    /// How many levels of parents above and subsidiaries below the company
+    /// to resolve.
+    pub depth: usize,
+    /// Stop resolving once this many records were bought. Records served
+    /// from the client's cache are free.
+    pub max_credits: Option<usize>,
+    /// Also resolve `affiliated_entities` as children.
+    pub include_affiliates: bool,
+}
+
+impl Default for HierarchyOptions {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            max_credits: Some(100),
+            include_affiliates: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyRelation {
+    /// The company the hierarchy was requested for.
+    Root,
+    Parent,
+    Subsidiary,
+    Affiliate,
+}
+
+#[derive(Debug)]
+pub struct HierarchyNode {
+    pub id: String,
+    /// How the node relates to the node above it.
+    pub relation: HierarchyRelation,
+    /// `None` when company enrichment found nothing or the credit limit
+    /// was reached first.
+    pub company: Option<CompanyResponse>,
+    /// Direct subsidiaries, then affiliates. Companies already elsewhere in
+    /// the tree are left out.
+    pub children: Vec<HierarchyNode>,
+}
+
+#[derive(Debug)]
+pub struct CompanyHierarchy {
+    /// The requested company and the companies below it.
+    pub root: HierarchyNode,
+    /// The chain of immediate parents above `root`, nearest first, without
+    /// children of their own.
+    pub parents: Vec<HierarchyNode>,
+    /// Records bought to build the hierarchy.
+    pub credits: usize,
+    /// The depth or credit limit left companies unresolved.
+    pub truncated: bool,
+}
+
+/// A node before the tree is assembled.
+struct Pending {
+    id: String,
+    relation: HierarchyRelation,
+    company: Option<CompanyResponse>,
+    children: Vec<usize>,
+}
+
+fn assemble(nodes: &mut Vec<Option<Pending>>, index: usize) -> HierarchyNode {
+    let pending = nodes[index].take().expect("each node has one parent");
+    HierarchyNode {
+        id: pending.id,
+        relation: pending.relation,
+        company: pending.company,
+        children: pending
+            .children
+            .into_iter()
+            .map(|child| assemble(nodes, child))
+            .collect(),
+    }
+}
+
+/// The ids below `company`, with how they relate to it.
+fn child_ids(
+    company: &CompanyResponse,
+    include_affiliates: bool,
+) -> Vec<(String, HierarchyRelation)> {
+    let subsidiaries = company
+        .direct_subsidiaries
+        .iter()
+        .flatten()
+        .map(|id| (id.clone(), HierarchyRelation::Subsidiary));
+    let affiliates = company
+        .affiliated_entities
+        .iter()
+        .flatten()
+        .filter(|_| include_affiliates)
+        .filter_map(|entity| entity.affiliated_id.clone())
+        .map(|id| (id, HierarchyRelation::Affiliate));
+    subsidiaries.chain(affiliates).collect()
+}
+
+/// Builds the hierarchy of `id`, resolving each level of ids with one call
+/// to `fetch` (ids, most to buy). Every id is resolved at most once, so
+/// cycles in the parent and subsidiary links end where they close.
+fn build<F>(
+    id: &str,
+    options: &HierarchyOptions,
+    mut fetch: F,
+) -> Result<CompanyHierarchy, PDLError>
+where
+    F: FnMut(Vec<String>, Option<usize>) -> Result<FetchedCompanies, PDLError>,
+{
+    let mut credits = 0;
+    let mut truncated = false;
+    let mut fetch_level = |ids: Vec<String>, truncated: &mut bool| {
+        let budget = options
+            .max_credits
+            .map(|max_credits| max_credits.saturating_sub(credits));
+        let fetched = fetch(ids, budget)?;
+        credits += fetched.credits;
+        *truncated |= !fetched.unsent.is_empty();
+        Ok::<_, PDLError>(fetched.companies)
+    };
+
+    let mut companies = fetch_level(vec![id.to_string()], &mut truncated)?;
+    let root_company = companies.remove(id);
+    if root_company.is_none() && !truncated {
+        return Err(PDLError::HTTPError(StatusCode::NOT_FOUND));
+    }
+    let mut visited = HashSet::from([id.to_string()]);
+
+    let mut parents = Vec::new();
+    let mut parent_id = root_company
+        .as_ref()
+        .and_then(|company| company.immediate_parent.clone());
+    while let Some(id) = parent_id.take() {
+        if parents.len() == options.depth {
+            truncated = true;
+            break;
+        }
+        if !visited.insert(id.clone()) {
+            break;
+        }
+        let company = fetch_level(vec![id.clone()], &mut truncated)?.remove(&id);
+        parent_id = company
+            .as_ref()
+            .and_then(|company| company.immediate_parent.clone());
+        parents.push(HierarchyNode {
+            id,
+            relation: HierarchyRelation::Parent,
+            company,
+            children: Vec::new(),
+        });
+    }
+
+    let mut nodes = vec![Some(Pending {
+        id: id.to_string(),
+        relation: HierarchyRelation::Root,
+        company: root_company,
+        children: Vec::new(),
+    })];
+    let mut level = vec![0];
+    for remaining in (0..=options.depth).rev() {
+        // (parent node, child id, relation) for every child not seen yet.
+        let mut edges = Vec::new();
+        for &parent in &level {
+            let Some(company) = nodes[parent]
+                .as_ref()
+                .and_then(|node| node.company.as_ref())
+            else {
+                continue;
+            };
+            for (child, relation) in child_ids(company, options.include_affiliates) {
+                if visited.insert(child.clone()) {
+                    edges.push((parent, child, relation));
+                }
+            }
+        }
+        if edges.is_empty() {
+            break;
+        }
+        if remaining == 0 {
+            truncated = true;
+            break;
+        }
+
+        let ids = edges.iter().map(|(_, child, _)| child.clone()).collect();
+        let mut companies = fetch_level(ids, &mut truncated)?;
+        level = Vec::with_capacity(edges.len());
+        for (parent, child, relation) in edges {
+            let index = nodes.len();
+            nodes.push(Some(Pending {
+                company: companies.remove(&child),
+                id: child,
+                relation,
+                children: Vec::new(),
+            }));
+            if let Some(parent) = nodes[parent].as_mut() {
+                parent.children.push(index);
+            }
+            level.push(index);
+        }
+    }
+
+    Ok(CompanyHierarchy {
+        root: assemble(&mut nodes, 0),
+        parents,
+        credits,
+        truncated,
+    })
+}
+
+impl Company {
+    /// Builds the corporate tree around the company with PDL id `id`: its
+    /// chain of parents and its subsidiaries, `depth` levels each way. See
+    /// `hierarchy_with_options` for the limits applied.
+    pub fn hierarchy(&self, id: &str, depth: usize) -> Result<CompanyHierarchy, PDLError> {
+        self.hierarchy_with_options(
+            id,
+            &HierarchyOptions {
+                depth,
+                ..HierarchyOptions::default()
+            },
+        )
+    }
+
+    /// Builds the corporate tree around the company with PDL id `id`,
+    /// resolving `immediate_parent`, `direct_subsidiaries` and optionally
+    /// `affiliated_entities` ids through company bulk enrichment, one batch
+    /// per level. Records are served from and added to the client's cache
+    /// like `Company::enrich` by `pdl_id`. A company linked more than once
+    /// appears once; `truncated` tells whether the depth or credit limit cut
+    /// the tree short.
+    pub fn hierarchy_with_options(
+        &self,
+        id: &str,
+        options: &HierarchyOptions,
+    ) -> Result<CompanyHierarchy, PDLError> {
+        build(id, options, |ids, max_sent| {
+            fetch_companies(&self.client, ids, max_sent, |requests| {
+                self.bulk_enrich(BulkEnrichCompanyParams {
+                    base_params: None,
+                    requests,
+                    additional_params: None,
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Fetches from a fixed set of records, billing a credit per record.
+    fn fetch_from(
+        records: &HashMap<&str, Value>,
+        calls: &mut Vec<Vec<String>>,
+        ids: Vec<String>,
+        max_sent: Option<usize>,
+    ) -> Result<FetchedCompanies, PDLError> {
+        calls.push(ids.clone());
+        let mut fetched = FetchedCompanies::default();
+        for id in ids {
+            if max_sent.is_some_and(|max_sent| fetched.credits >= max_sent) {
+                fetched.unsent.push(id);
+            } else if let Some(record) = records.get(id.as_str()) {
+                fetched.credits += 1;
+                fetched
+                    .companies
+                    .insert(id, serde_json::from_value(record.clone()).expect("ERROR"));
+            }
+        }
+        Ok(fetched)
+    }
+
+    fn records() -> HashMap<&'static str, Value> {
+        HashMap::from([
+            ("top", json!({"id": "top", "direct_subsidiaries": ["mid"]})),
+            (
+                "mid",
+                json!({
+                    "id": "mid",
+                    "immediate_parent": "top",
+                    "direct_subsidiaries": ["a", "b"],
+                    "affiliated_entities": [{"affiliated_id": "partner"}],
+                }),
+            ),
+            (
+                "a",
+                json!({"id": "a", "immediate_parent": "mid", "direct_subsidiaries": ["a1", "mid"]}),
+            ),
+            ("b", json!({"id": "b", "immediate_parent": "mid"})),
+            ("a1", json!({"id": "a1", "immediate_parent": "a"})),
+            ("partner", json!({"id": "partner"})),
+        ])
+    }
+
+    fn ids(nodes: &[HierarchyNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_hierarchy_resolves_each_level_in_one_batch() {
+        let records = records();
+        let mut calls = Vec::new();
+        let options = HierarchyOptions {
+            depth: 2,
+            max_credits: None,
+            include_affiliates: true,
+        };
+        let hierarchy = build("mid", &options, |ids, max_sent| {
+            fetch_from(&records, &mut calls, ids, max_sent)
+        })
+        .expect("ERROR");
+
+        assert_eq!(ids(&hierarchy.parents), vec!["top"]);
+        assert_eq!(ids(&hierarchy.root.children), vec!["a", "b", "partner"]);
+        assert_eq!(
+            hierarchy.root.children[2].relation,
+            HierarchyRelation::Affiliate
+        );
+        // "mid" under "a" closes a cycle and is left out.
+        assert_eq!(ids(&hierarchy.root.children[0].children), vec!["a1"]);
+        assert_eq!(
+            calls,
+            vec![
+                vec!["mid".to_string()],
+                vec!["top".to_string()],
+                vec!["a".to_string(), "b".to_string(), "partner".to_string()],
+                vec!["a1".to_string()],
+            ]
+        );
+        assert_eq!(hierarchy.credits, 6);
+        assert!(!hierarchy.truncated);
+    }
+
+    #[test]
+    fn test_hierarchy_respects_depth_and_credit_limits() {
+        let records = records();
+        let mut calls = Vec::new();
+        let options = HierarchyOptions {
+            depth: 1,
+            ..HierarchyOptions::default()
+        };
+        let hierarchy = build("mid", &options, |ids, max_sent| {
+            fetch_from(&records, &mut calls, ids, max_sent)
+        })
+        .expect("ERROR");
+        assert_eq!(ids(&hierarchy.root.children), vec!["a", "b"]);
+        assert!(hierarchy.root.children[0].children.is_empty());
+        assert!(hierarchy.truncated);
+
+        let options = HierarchyOptions {
+            depth: 3,
+            max_credits: Some(3),
+            include_affiliates: false,
+        };
+        let hierarchy = build("mid", &options, |ids, max_sent| {
+            fetch_from(&records, &mut calls, ids, max_sent)
+        })
+        .expect("ERROR");
+        assert_eq!(hierarchy.credits, 3);
+        assert!(hierarchy.truncated);
+        let children = &hierarchy.root.children;
+        assert!(children[0].company.is_some());
+        assert!(children[1].company.is_none());
+    }
+
+    #[test]
+    fn test_hierarchy_of_unknown_company() {
+        let records = records();
+        let mut calls = Vec::new();
+        let result = build("nope", &HierarchyOptions::default(), |ids, max_sent| {
+            fetch_from(&records, &mut calls, ids, max_sent)
+        });
+        assert!(matches!(
+            result,
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+        ));
+    }
+}
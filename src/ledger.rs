@@ -0,0 +1,729 @@
+//! Ledger of enrichment inputs and outcomes, kept across process runs.
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    cache::canonical_params,
+    models::{
+        company::{BulkCompanyEnrichResponse, CompanyResponse},
+        person::{BulkEnrichPersonResponse, EnrichPersonResponse},
+    },
+    util::{sha256_hex, unix_seconds},
+    PDLError,
+};
+
+const DEFAULT_FRESHNESS: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// What an enrichment returned: a match (status 200 with `data`) or no
+/// match (status 404).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct LedgerOutcome {
+    status: i32,
+    likelihood: Option<i32>,
+    data: Option<Value>,
+}
+
+impl LedgerOutcome {
+    fn not_found() -> Self {
+        LedgerOutcome {
+            status: StatusCode::NOT_FOUND.as_u16().into(),
+            likelihood: None,
+            data: None,
+        }
+    }
+
+    fn data<T: serde::de::DeserializeOwned>(self) -> Result<Option<T>, PDLError> {
+        self.data
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|_| PDLError::SerializationError)
+    }
+}
+
+/// A response whose outcome the ledger can record and replay.
+pub(crate) trait LedgerRecord: Sized {
+    /// The outcome to record, or `None` for responses that shouldn't be
+    /// remembered (errors other than "no match").
+    fn outcome(&self) -> Option<LedgerOutcome>;
+
+    fn replay(outcome: LedgerOutcome) -> Result<Self, PDLError>;
+}
+
+fn recordable(status: i32) -> bool {
+    status == 200 || status == 404
+}
+
+fn to_data<T: Serialize>(data: &T) -> Option<Value> {
+    serde_json::to_value(data).ok()
+}
+
+fn not_found_error() -> PDLError {
+    PDLError::HTTPError(StatusCode::NOT_FOUND)
+}
+
+impl LedgerRecord for EnrichPersonResponse {
+    fn outcome(&self) -> Option<LedgerOutcome> {
+        Some(LedgerOutcome {
+            status: self.status,
+            likelihood: Some(self.likelihood),
+            data: Some(to_data(&self.data)?),
+        })
+        .filter(|outcome| recordable(outcome.status))
+    }
+
+    fn replay(outcome: LedgerOutcome) -> Result<Self, PDLError> {
+        let (status, likelihood) = (outcome.status, outcome.likelihood.unwrap_or_default());
+        match outcome.data()? {
+            Some(data) if status == 200 => Ok(EnrichPersonResponse {
+                status,
+                likelihood,
+                data,
+            }),
+            _ => Err(not_found_error()),
+        }
+    }
+}
+
+impl LedgerRecord for BulkEnrichPersonResponse {
+    fn outcome(&self) -> Option<LedgerOutcome> {
+        Some(LedgerOutcome {
+            status: self.status,
+            likelihood: self.likelihood,
+            data: self.data.as_ref().and_then(to_data),
+        })
+        .filter(|outcome| recordable(outcome.status))
+    }
+
+    fn replay(outcome: LedgerOutcome) -> Result<Self, PDLError> {
+        Ok(BulkEnrichPersonResponse {
+            status: outcome.status,
+            likelihood: outcome.likelihood,
+            data: outcome.data()?,
+            metadata: None,
+        })
+    }
+}
+
+impl LedgerRecord for CompanyResponse {
+    fn outcome(&self) -> Option<LedgerOutcome> {
+        Some(LedgerOutcome {
+            status: self.status.unwrap_or(200),
+            likelihood: self.likelihood,
+            data: Some(to_data(self)?),
+        })
+        .filter(|outcome| recordable(outcome.status))
+    }
+
+    fn replay(outcome: LedgerOutcome) -> Result<Self, PDLError> {
+        match outcome.status {
+            200 => outcome.data()?.ok_or(PDLError::SerializationError),
+            _ => Err(not_found_error()),
+        }
+    }
+}
+
+impl LedgerRecord for BulkCompanyEnrichResponse {
+    fn outcome(&self) -> Option<LedgerOutcome> {
+        let record = self.data.as_ref().and_then(|data| data.first());
+        Some(LedgerOutcome {
+            status: self.status,
+            likelihood: self.likelihood,
+            data: record.and_then(to_data),
+        })
+        .filter(|outcome| recordable(outcome.status))
+    }
+
+    fn replay(outcome: LedgerOutcome) -> Result<Self, PDLError> {
+        Ok(BulkCompanyEnrichResponse {
+            status: outcome.status,
+            likelihood: outcome.likelihood,
+            data: outcome
+                .data::<CompanyResponse>()?
+                .map(|record| vec![record]),
+            metadata: None,
+        })
+    }
+}
+
+/// One ledger line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LedgerEntry {
+    fingerprint: String,
+    kind: String,
+    recorded_at: u64,
+    outcome: LedgerOutcome,
+}
+
+#[cfg(feature = "store")]
+fn ledger_error(error: impl std::fmt::Display) -> PDLError {
+    PDLError::StoreError(error.to_string())
+}
+
+#[derive(Debug)]
+enum Backend {
+    /// Entries live only in `LedgerState::entries`.
+    Memory,
+    /// Entries are appended to the file, one JSON object per line; later
+    /// lines replace earlier ones with the same fingerprint.
+    Jsonl(File),
+    #[cfg(feature = "store")]
+    Sqlite(rusqlite::Connection),
+}
+
+#[derive(Debug)]
+struct LedgerState {
+    backend: Backend,
+    entries: HashMap<String, LedgerEntry>,
+}
+
+/// Remembers which people and companies were already enriched, by a
+/// fingerprint of their input, and what came back. Set it through
+/// `PDLCLientOptions.ledger`: `Person::enrich`, `Person::bulk_enrich`,
+/// `Company::enrich` and `Company::bulk_enrich` then return the recorded
+/// outcome for inputs seen within the freshness window (30 days by
+/// default) instead of calling the API, and record every new match or
+/// no-match.
+///
+/// Fingerprints cover the normalized params and the options that change the
+/// result (`required`, `min_likelihood`, `data_include`, ...), but not
+/// `pretty` or bulk `metadata`, so a person enriched alone is recognized in
+/// a later bulk request. A replayed bulk response carries its request's
+/// metadata.
+#[derive(Clone, Debug)]
+pub struct EnrichmentLedger {
+    state: Arc<Mutex<LedgerState>>,
+    freshness: Duration,
+}
+
+impl EnrichmentLedger {
+    /// A ledger that's forgotten when the process exits.
+    pub fn in_memory() -> Self {
+        Self::with_backend(Backend::Memory, HashMap::new())
+    }
+
+    /// A ledger in a JSON Lines file, created if needed. Unreadable lines
+    /// (e.g. one cut short by a crash) are skipped.
+    pub fn open_jsonl(path: impl AsRef<Path>) -> Result<Self, PDLError> {
+        let path = path.as_ref();
+        let mut entries = HashMap::new();
+        let mut ends_with_newline = true;
+        if path.exists() {
+            let reader = BufReader::new(File::open(path).map_err(PDLError::IOError)?);
+            for line in reader.split(b'\n') {
+                let line = line.map_err(PDLError::IOError)?;
+                ends_with_newline = line.is_empty();
+                if let Ok(entry) = serde_json::from_slice::<LedgerEntry>(&line) {
+                    entries.insert(entry.fingerprint.clone(), entry);
+                }
+            }
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(PDLError::IOError)?;
+        // Start a fresh line after a partial one.
+        if !ends_with_newline {
+            file.write_all(b"\n").map_err(PDLError::IOError)?;
+        }
+        Ok(Self::with_backend(Backend::Jsonl(file), entries))
+    }
+
+    /// A ledger in a SQLite database, which may be the same file as a
+    /// `RecordStore`.
+    #[cfg(feature = "store")]
+    pub fn open_sqlite(path: impl AsRef<Path>) -> Result<Self, PDLError> {
+        let connection = rusqlite::Connection::open(path).map_err(ledger_error)?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS enrichment_ledger (
+                    fingerprint TEXT PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    recorded_at INTEGER NOT NULL,
+                    outcome TEXT NOT NULL
+                )",
+            )
+            .map_err(ledger_error)?;
+
+        let mut entries = HashMap::new();
+        {
+            let mut statement = connection
+                .prepare("SELECT fingerprint, kind, recorded_at, outcome FROM enrichment_ledger")
+                .map_err(ledger_error)?;
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })
+                .map_err(ledger_error)?;
+            for row in rows {
+                let (fingerprint, kind, recorded_at, outcome) = row.map_err(ledger_error)?;
+                if let Ok(outcome) = serde_json::from_str(&outcome) {
+                    let entry = LedgerEntry {
+                        fingerprint: fingerprint.clone(),
+                        kind,
+                        recorded_at: recorded_at.max(0) as u64,
+                        outcome,
+                    };
+                    entries.insert(fingerprint, entry);
+                }
+            }
+        }
+        Ok(Self::with_backend(Backend::Sqlite(connection), entries))
+    }
+
+    fn with_backend(backend: Backend, entries: HashMap<String, LedgerEntry>) -> Self {
+        EnrichmentLedger {
+            state: Arc::new(Mutex::new(LedgerState { backend, entries })),
+            freshness: DEFAULT_FRESHNESS,
+        }
+    }
+
+    /// How long a recorded outcome is reused; older inputs are enriched
+    /// again and their entry replaced.
+    pub fn freshness(mut self, freshness: Duration) -> Self {
+        self.freshness = freshness;
+        self
+    }
+
+    /// The number of recorded inputs, fresh or not.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LedgerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The fingerprint of a `kind` ("person" or "company") enrichment of
+    /// `request`, which serializes to a JSON object of params and options.
+    pub(crate) fn fingerprint<R: Serialize>(kind: &str, request: &R) -> Result<String, PDLError> {
+        let mut request =
+            serde_json::to_value(request).map_err(|_| PDLError::SerializationError)?;
+        if let Value::Object(object) = &mut request {
+            object.remove("pretty");
+            object.remove("metadata");
+        }
+        let text = format!("{}\n{}", kind, canonical_params(request, true));
+        Ok(sha256_hex(text.as_bytes()))
+    }
+
+    /// The outcome recorded for `fingerprint` within the freshness window.
+    fn recall(&self, fingerprint: &str) -> Option<LedgerOutcome> {
+        let oldest = unix_seconds(SystemTime::now()).saturating_sub(self.freshness.as_secs());
+        self.lock()
+            .entries
+            .get(fingerprint)
+            .filter(|entry| entry.recorded_at >= oldest)
+            .map(|entry| entry.outcome.clone())
+    }
+
+    fn record(
+        &self,
+        kind: &str,
+        fingerprint: String,
+        outcome: LedgerOutcome,
+    ) -> Result<(), PDLError> {
+        let entry = LedgerEntry {
+            fingerprint: fingerprint.clone(),
+            kind: kind.to_string(),
+            recorded_at: unix_seconds(SystemTime::now()),
+            outcome,
+        };
+        let mut state = self.lock();
+        match &mut state.backend {
+            Backend::Memory => {}
+            Backend::Jsonl(file) => {
+                let mut line =
+                    serde_json::to_string(&entry).map_err(|_| PDLError::SerializationError)?;
+                line.push('\n');
+                file.write_all(line.as_bytes()).map_err(PDLError::IOError)?;
+                file.flush().map_err(PDLError::IOError)?;
+            }
+            #[cfg(feature = "store")]
+            Backend::Sqlite(connection) => {
+                let outcome = serde_json::to_string(&entry.outcome)
+                    .map_err(|_| PDLError::SerializationError)?;
+                connection
+                    .execute(
+                        "INSERT OR REPLACE INTO enrichment_ledger
+                             (fingerprint, kind, recorded_at, outcome)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        rusqlite::params![
+                            entry.fingerprint,
+                            entry.kind,
+                            entry.recorded_at as i64,
+                            outcome
+                        ],
+                    )
+                    .map_err(ledger_error)?;
+            }
+        }
+        state.entries.insert(fingerprint, entry);
+        Ok(())
+    }
+
+    /// Returns the recorded outcome for `fingerprint` if it's fresh, or
+    /// calls `send` and records its match or no-match. `send` tells whether
+    /// its response was replayed (from a cache, say), and so does `run`:
+    /// `true` for a recorded outcome.
+    pub(crate) fn run<T, F>(
+        &self,
+        kind: &str,
+        fingerprint: String,
+        send: F,
+    ) -> Result<(T, bool), PDLError>
+    where
+        T: LedgerRecord,
+        F: FnOnce() -> Result<(T, bool), PDLError>,
+    {
+        if let Some(outcome) = self.recall(&fingerprint) {
+            return T::replay(outcome).map(|response| (response, true));
+        }
+        match send() {
+            Ok((response, replayed)) => {
+                if let Some(outcome) = response.outcome() {
+                    self.record(kind, fingerprint, outcome)?;
+                }
+                Ok((response, replayed))
+            }
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => {
+                self.record(kind, fingerprint, LedgerOutcome::not_found())?;
+                Err(not_found_error())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Bulk version of `run`: requests with a fresh outcome are answered
+    /// from the ledger through `replay`, the rest go through `send` in one
+    /// call, and the responses come back in request order. `send` must
+    /// answer every request it's given.
+    pub(crate) fn run_bulk<R, T, F>(
+        &self,
+        kind: &str,
+        requests: Vec<(String, R)>,
+        replay: impl Fn(&R, T) -> T,
+        send: F,
+    ) -> Result<Vec<T>, PDLError>
+    where
+        T: LedgerRecord,
+        F: FnOnce(Vec<R>) -> Result<Vec<T>, PDLError>,
+    {
+        let mut responses: Vec<Option<T>> = Vec::with_capacity(requests.len());
+        let mut pending = Vec::new();
+        for (fingerprint, request) in requests {
+            match self.recall(&fingerprint) {
+                Some(outcome) => responses.push(Some(replay(&request, T::replay(outcome)?))),
+                None => {
+                    pending.push((responses.len(), fingerprint, request));
+                    responses.push(None);
+                }
+            }
+        }
+
+        if !pending.is_empty() {
+            let (slots, requests): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .map(|(slot, fingerprint, request)| ((slot, fingerprint), request))
+                .unzip();
+            let sent = send(requests)?;
+            if sent.len() != slots.len() {
+                return Err(PDLError::SerializationError);
+            }
+            for ((slot, fingerprint), response) in slots.into_iter().zip(sent) {
+                if let Some(outcome) = response.outcome() {
+                    self.record(kind, fingerprint, outcome)?;
+                }
+                responses[slot] = Some(response);
+            }
+        }
+        Ok(responses.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn person_response(id: &str) -> EnrichPersonResponse {
+        serde_json::from_value(json!({"status": 200, "likelihood": 8, "data": {"id": id}})).unwrap()
+    }
+
+    fn bulk_person_response(status: i32, id: Option<&str>) -> BulkEnrichPersonResponse {
+        serde_json::from_value(json!({
+            "status": status,
+            "likelihood": id.map(|_| 7),
+            "data": id.map(|id| json!({"id": id})),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_order_pretty_and_metadata() {
+        let fingerprint = |kind, request| EnrichmentLedger::fingerprint(kind, &request).unwrap();
+        let a = fingerprint(
+            "person",
+            json!({"email": ["a@x.com", "b@x.com"], "pretty": true}),
+        );
+        let b = fingerprint(
+            "person",
+            json!({"metadata": {"row": "1"}, "email": ["b@x.com", "a@x.com"]}),
+        );
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            fingerprint("company", json!({"email": ["a@x.com", "b@x.com"]}))
+        );
+        assert_ne!(
+            a,
+            fingerprint(
+                "person",
+                json!({"email": ["a@x.com", "b@x.com"], "required": "emails"})
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_replays_matches_and_no_matches() {
+        let ledger = EnrichmentLedger::in_memory();
+        let calls = Cell::new(0);
+
+        for round in 0..2 {
+            let (response, replayed) = ledger
+                .run("person", "jane".to_string(), || {
+                    calls.set(calls.get() + 1);
+                    Ok((person_response("jane-id"), false))
+                })
+                .unwrap();
+            assert_eq!(response.data.id.as_deref(), Some("jane-id"));
+            assert_eq!(response.likelihood, 8);
+            assert_eq!(replayed, round > 0);
+        }
+
+        for _ in 0..2 {
+            let result =
+                ledger.run::<EnrichPersonResponse, _>("person", "nobody".to_string(), || {
+                    calls.set(calls.get() + 1);
+                    Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+                });
+            assert!(matches!(
+                result,
+                Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+            ));
+        }
+
+        // Other errors aren't remembered.
+        for _ in 0..2 {
+            let result =
+                ledger.run::<EnrichPersonResponse, _>("person", "limited".to_string(), || {
+                    calls.set(calls.get() + 1);
+                    Err(PDLError::HTTPError(StatusCode::TOO_MANY_REQUESTS))
+                });
+            assert!(result.is_err());
+        }
+        assert_eq!(calls.get(), 4);
+        assert_eq!(ledger.len(), 2);
+    }
+
+    #[test]
+    fn test_stale_outcomes_are_enriched_again() {
+        let ledger = EnrichmentLedger::in_memory().freshness(Duration::ZERO);
+        ledger
+            .record(
+                "person",
+                "jane".to_string(),
+                person_response("old").outcome().unwrap(),
+            )
+            .unwrap();
+        ledger.lock().entries.get_mut("jane").unwrap().recorded_at -= 10;
+
+        let (response, _) = ledger
+            .run("person", "jane".to_string(), || {
+                Ok((person_response("new"), false))
+            })
+            .unwrap();
+        assert_eq!(response.data.id.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_run_bulk_sends_only_unseen_requests_in_order() {
+        let ledger = EnrichmentLedger::in_memory();
+        ledger
+            .record(
+                "person",
+                "b".to_string(),
+                person_response("b-id").outcome().unwrap(),
+            )
+            .unwrap();
+        ledger
+            .record("person", "d".to_string(), LedgerOutcome::not_found())
+            .unwrap();
+
+        let requests = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| (name.to_string(), name.to_string()))
+            .collect();
+        let mut sent = Vec::new();
+        let responses = ledger
+            .run_bulk(
+                "person",
+                requests,
+                |request: &String, mut response: BulkEnrichPersonResponse| {
+                    response.metadata = Some([("row".to_string(), request.clone())].into());
+                    response
+                },
+                |requests| {
+                    sent = requests.clone();
+                    Ok(vec![
+                        bulk_person_response(200, Some("a-id")),
+                        bulk_person_response(404, None),
+                    ])
+                },
+            )
+            .unwrap();
+
+        assert_eq!(sent, vec!["a".to_string(), "c".to_string()]);
+        let summary: Vec<(i32, Option<String>)> = responses
+            .iter()
+            .map(|response| {
+                (
+                    response.status,
+                    response.data.as_ref().and_then(|data| data.id.clone()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (200, Some("a-id".to_string())),
+                (200, Some("b-id".to_string())),
+                (404, None),
+                (404, None),
+            ]
+        );
+        assert_eq!(responses[1].metadata.as_ref().unwrap()["row"], "b");
+        assert!(responses[0].metadata.is_none());
+        // "a" and "c" were recorded.
+        assert_eq!(ledger.len(), 4);
+    }
+
+    #[test]
+    fn test_run_bulk_rejects_short_responses() {
+        let ledger = EnrichmentLedger::in_memory();
+        let requests = ["a", "b"]
+            .iter()
+            .map(|name| (name.to_string(), name.to_string()))
+            .collect();
+        let result = ledger.run_bulk(
+            "person",
+            requests,
+            |_: &String, response: BulkEnrichPersonResponse| response,
+            |_| Ok(vec![bulk_person_response(200, Some("a-id"))]),
+        );
+        assert!(matches!(result, Err(PDLError::SerializationError)));
+        assert!(ledger.is_empty());
+    }
+
+    #[test]
+    fn test_company_outcomes_are_shared_between_single_and_bulk() {
+        let ledger = EnrichmentLedger::in_memory();
+        let company: CompanyResponse =
+            serde_json::from_value(json!({"status": 200, "likelihood": 9, "id": "acme-id"}))
+                .unwrap();
+        ledger
+            .run("company", "acme".to_string(), || Ok((company, false)))
+            .unwrap();
+
+        let responses = ledger
+            .run_bulk(
+                "company",
+                vec![("acme".to_string(), ())],
+                |_, response: BulkCompanyEnrichResponse| response,
+                |_| panic!("already recorded"),
+            )
+            .unwrap();
+        let data = responses[0].data.as_ref().unwrap();
+        assert_eq!(data[0].id.as_deref(), Some("acme-id"));
+        assert_eq!(responses[0].likelihood, Some(9));
+    }
+
+    #[test]
+    fn test_jsonl_ledger_survives_reopening() {
+        let path = std::env::temp_dir().join(format!(
+            "peopledatalabs-ledger-{}.jsonl",
+            std::process::id()
+        ));
+        {
+            let ledger = EnrichmentLedger::open_jsonl(&path).unwrap();
+            ledger
+                .run("person", "jane".to_string(), || {
+                    Ok((person_response("jane-id"), false))
+                })
+                .unwrap();
+        }
+        // A line cut short by a crash is skipped.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"fingerprint\": \"trunc").unwrap();
+        drop(file);
+
+        let ledger = EnrichmentLedger::open_jsonl(&path).unwrap();
+        assert_eq!(ledger.len(), 1);
+        let (response, replayed) = ledger
+            .run::<EnrichPersonResponse, _>("person", "jane".to_string(), || panic!("recorded"))
+            .unwrap();
+        assert!(replayed);
+        assert_eq!(response.data.id.as_deref(), Some("jane-id"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "store")]
+    #[test]
+    fn test_sqlite_ledger_survives_reopening() {
+        let path = std::env::temp_dir().join(format!(
+            "peopledatalabs-ledger-{}.sqlite",
+            std::process::id()
+        ));
+        {
+            let ledger = EnrichmentLedger::open_sqlite(&path).unwrap();
+            ledger
+                .run::<EnrichPersonResponse, _>("person", "nobody".to_string(), || {
+                    Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+                })
+                .unwrap_err();
+        }
+        let ledger = EnrichmentLedger::open_sqlite(&path).unwrap();
+        let result = ledger
+            .run::<EnrichPersonResponse, _>("person", "nobody".to_string(), || panic!("recorded"));
+        assert!(matches!(
+            result,
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,20 +1,74 @@
+#[macro_use]
+mod macros;
+
 mod api;
+#[cfg(feature = "arrow")]
+mod arrow_export;
+mod batch_job;
+mod cache;
 mod client;
+#[cfg(feature = "csv")]
+mod csv_enrich;
+mod decision_makers;
+mod fanout;
+mod flatten;
+mod hierarchy;
+mod ledger;
+mod lookalike;
 mod models;
+mod normalize;
+#[cfg(feature = "store")]
+mod store;
+mod util;
+mod waterfall;
 
 use api::{Autocomplete, Changelog, Company, JobPosting, JobTitle, Location, Person, School, IP};
-use client::{PDLClient, PDLError};
+#[cfg(feature = "arrow")]
+pub use arrow_export::{to_record_batch, ArrowRecord, ParquetSink};
+pub use batch_job::{BatchJobOptions, BatchJobSummary, JobInputFormat, JobOutputRow};
+pub use cache::{CacheStats, ResponseCache};
+pub use client::{PDLCLientOptions, PDLClient, PDLError, RequestMode};
+#[cfg(feature = "csv")]
+pub use csv_enrich::{ColumnMapping, CsvEnrichOptions, CsvEnrichSummary};
+pub use decision_makers::{
+    CompanyDecisionMakers, DecisionMakerQuery, DecisionMakers, TargetCompany,
+};
+pub use fanout::WithCompanies;
+pub use flatten::{
+    flatten_record, flatten_records, ArrayHandling, FlatTable, FlattenOptions, RECORDS_TABLE,
+};
+pub use hierarchy::{CompanyHierarchy, HierarchyNode, HierarchyOptions, HierarchyRelation};
+pub use ledger::EnrichmentLedger;
+pub use lookalike::{CompanyCriterion, CompanySimilarity, PersonSimilarity};
+pub use normalize::{
+    canonicalize_profile, hash_email, normalize_email, normalize_phone, parse_address, parse_name,
+    EmailHashAlgorithm, EmailHashing, EmailNormalization, ParsedAddress, ParsedName, ProfileKind,
+    SocialNetwork, SocialProfile,
+};
+#[cfg(feature = "store")]
+pub use store::{Lookup, RecordStore, StorableRecord, StoredRecord};
+pub use waterfall::{
+    WaterfallAttempt, WaterfallOptions, WaterfallOutcome, WaterfallResult, WaterfallStep,
+};
 
 pub use models::{
-    AutocompleteBaseParams, AutocompleteParams, BaseParams, BulkEnrichPersonParams,
-    BulkEnrichSinglePersonParams, BulkRetrievePersonParams, BulkRetrieveSinglePersonParams,
-    CleanCompanyParams, CleanLocationParams, CleanSchoolParams, CompanyParams, EnrichCompanyParams,
-    EnrichPersonParams, IPBaseParams, IPParams, IdentifyPersonParams, JobPostingSearchBaseParams,
-    JobPostingSearchParams, JobTitleBaseParams, JobTitleParams, LocationParams, PersonMetadata,
-    PersonParams, RemoteWorkPolicy, RetrievePersonParams, SalaryPeriod, SchoolParams,
-    SearchBaseParams, SearchParams,
+    AdditionalParams, AutocompleteBaseParams, AutocompleteParams, AutocompleteParamsBuilder,
+    BaseParams, BulkEnrichCompanyParams, BulkEnrichCompanyParamsBuilder, BulkEnrichPersonParams,
+    BulkEnrichPersonParamsBuilder, BulkEnrichSingleCompanyParams, BulkEnrichSinglePersonParams,
+    BulkResult, BulkRetrievePersonParams, BulkRetrievePersonParamsBuilder,
+    BulkRetrieveSinglePersonParams, BulkValidation, CleanCompanyParams, CleanCompanyParamsBuilder,
+    CleanLocationParams, CleanLocationParamsBuilder, CleanSchoolParams, CleanSchoolParamsBuilder,
+    CompanyMetadata, CompanyParams, CompanyParamsBuilder, ConfidenceLevel, EnrichCompanyParams,
+    EnrichCompanyParamsBuilder, EnrichPersonParams, EnrichPersonParamsBuilder, IPBaseParams,
+    IPParams, IPParamsBuilder, IdentifyPersonParams, IdentifyPersonParamsBuilder,
+    JobPostingSearchBaseParams, JobPostingSearchParams, JobPostingSearchParamsBuilder,
+    JobTitleBaseParams, JobTitleParams, JobTitleParamsBuilder, LocationParams, MultiValueEncoding,
+    PersonField, PersonInputRule, PersonMetadata, PersonParams, PersonParamsBuilder, QueryEncoding,
+    RejectedRequest, RemoteWorkPolicy, RetrievePersonParams, RetrievePersonParamsBuilder,
+    SalaryPeriod, SchoolParams, SearchBaseParams, SearchParams, SearchParamsBuilder,
+    PERSON_INPUT_RULES,
 };
-pub use models::changelog::ChangelogPersonParams;
+pub use models::changelog::{ChangelogPersonParams, ChangelogPersonParamsBuilder};
 
 pub struct PDL {
     pub autocomplete: Autocomplete,
@@ -37,8 +91,12 @@ impl Default for PDL {
 impl PDL {
     pub fn new() -> PDL {
         let api_key = std::env::var("PDL_API_KEY").unwrap();
-        let client = PDLClient::new(&api_key).build();
+        Self::with_client(PDLClient::new(&api_key).build())
+    }
 
+    /// Builds the API handles around an already configured client, e.g. one
+    /// with sandbox or request options set.
+    pub fn with_client(client: PDLClient) -> PDL {
         PDL {
             autocomplete: Autocomplete {
                 client: client.clone(),
@@ -0,0 +1,428 @@
+//! Search queries for people and companies that resemble a seed record.
+
+use serde_json::{json, Value};
+
+use crate::{
+    models::{
+        common::{SearchBaseParams, SearchParams},
+        company::CompanyResponse,
+        person::Person as PersonRecord,
+    },
+    PDLError,
+};
+
+/// What a person must share with a seed record to count as alike. A
+/// criterion the seed has no data for is left out of the query.
+#[derive(Clone, Debug)]
+pub struct PersonSimilarity {
+    /// Same `job_title_role`.
+    pub same_role: bool,
+    /// At least one of the seed's `job_title_levels`.
+    pub same_levels: bool,
+    /// Same `industry`.
+    pub same_industry: bool,
+    /// Within this many kilometers of the seed's `location_geo`.
+    pub location_radius_km: Option<f64>,
+    /// At least this many of the seed's `skills` (fewer if the seed has
+    /// fewer); 0 ignores skills.
+    pub min_skill_overlap: usize,
+    /// Leave the seed itself out of the results.
+    pub exclude_seed: bool,
+    /// Leave these PDL ids out too, e.g. people already seen.
+    pub exclude_ids: Vec<String>,
+}
+
+impl Default for PersonSimilarity {
+    fn default() -> Self {
+        Self {
+            same_role: true,
+            same_levels: true,
+            same_industry: true,
+            location_radius_km: None,
+            min_skill_overlap: 2,
+            exclude_seed: true,
+            exclude_ids: Vec::new(),
+        }
+    }
+}
+
+impl PersonSimilarity {
+    /// The Elasticsearch query for people like `seed`. Fails with
+    /// `ValidationError` when the seed has none of the data the profile
+    /// compares.
+    pub fn query(&self, seed: &PersonRecord) -> Result<Value, PDLError> {
+        let mut must = Vec::new();
+        if let Some(role) = seed.job_title_role.as_ref().filter(|_| self.same_role) {
+            must.push(json!({ "term": { "job_title_role": role } }));
+        }
+        if let Some(levels) = seed
+            .job_title_levels
+            .as_ref()
+            .filter(|levels| self.same_levels && !levels.is_empty())
+        {
+            must.push(json!({ "terms": { "job_title_levels": levels } }));
+        }
+        if let Some(industry) = seed.industry.as_ref().filter(|_| self.same_industry) {
+            must.push(json!({ "term": { "industry": industry } }));
+        }
+        if let (Some(radius), Some(geo)) = (self.location_radius_km, &seed.location_geo) {
+            must.push(json!({
+                "geo_distance": { "distance": format!("{}km", radius), "location_geo": geo }
+            }));
+        }
+        let skills = seed.skills.as_deref().unwrap_or_default();
+        if self.min_skill_overlap > 0 && !skills.is_empty() {
+            let should: Vec<Value> = skills
+                .iter()
+                .map(|skill| json!({ "term": { "skills": skill } }))
+                .collect();
+            must.push(json!({
+                "bool": {
+                    "should": should,
+                    "minimum_should_match": self.min_skill_overlap.min(skills.len()),
+                }
+            }));
+        }
+        if must.is_empty() {
+            return Err(PDLError::ValidationError);
+        }
+
+        let mut query = json!({ "bool": { "must": must } });
+        let excluded = exclusions(seed.id.as_ref(), self.exclude_seed, &self.exclude_ids);
+        if !excluded.is_empty() {
+            query["bool"]["must_not"] = json!([{ "terms": { "id": excluded } }]);
+        }
+        Ok(query)
+    }
+
+    /// `query` as `SearchParams` for `Person::search`.
+    pub fn search_params(&self, seed: &PersonRecord) -> Result<SearchParams, PDLError> {
+        Ok(search_params(self.query(seed)?))
+    }
+}
+
+/// A company attribute lookalike companies can share with the seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompanyCriterion {
+    /// `industry` or `industry_v2`.
+    Industry,
+    /// The `size` band, e.g. `"51-200"`.
+    Size,
+    /// Any of the seed's NAICS codes.
+    Naics,
+    /// Any of the seed's SIC codes.
+    Sic,
+    /// `latest_funding_stage`.
+    FundingStage,
+    /// Any of the seed's `technologies_used`.
+    Technologies,
+    /// `location.country`.
+    Country,
+}
+
+impl CompanyCriterion {
+    /// The clause matching what `seed` has for the criterion, boosted by
+    /// `weight`, or `None` if the seed has no data for it.
+    fn clause(&self, seed: &CompanyResponse, weight: f32) -> Option<Value> {
+        fn term(field: &str, value: &str, weight: f32) -> Value {
+            json!({ "term": { field: { "value": value, "boost": weight } } })
+        }
+        fn terms(field: &str, values: Vec<&String>, weight: f32) -> Option<Value> {
+            (!values.is_empty()).then(|| json!({ "terms": { field: values, "boost": weight } }))
+        }
+
+        match self {
+            CompanyCriterion::Industry => {
+                let should: Vec<Value> = [
+                    ("industry", &seed.industry),
+                    ("industry_v2", &seed.industry_v2),
+                ]
+                .into_iter()
+                .filter_map(|(field, value)| Some(term(field, value.as_ref()?, weight)))
+                .collect();
+                (!should.is_empty()).then(|| json!({ "bool": { "should": should } }))
+            }
+            CompanyCriterion::Size => seed.size.as_ref().map(|size| term("size", size, weight)),
+            CompanyCriterion::Naics => terms(
+                "naics.naics_code",
+                seed.naics
+                    .iter()
+                    .flatten()
+                    .filter_map(|naics| naics.naics_code.as_ref())
+                    .collect(),
+                weight,
+            ),
+            CompanyCriterion::Sic => terms(
+                "sic.sic_code",
+                seed.sic
+                    .iter()
+                    .flatten()
+                    .filter_map(|sic| sic.sic_code.as_ref())
+                    .collect(),
+                weight,
+            ),
+            CompanyCriterion::FundingStage => seed
+                .latest_funding_stage
+                .as_ref()
+                .map(|stage| term("latest_funding_stage", stage, weight)),
+            CompanyCriterion::Technologies => terms(
+                "technologies_used.name",
+                seed.technologies_used
+                    .iter()
+                    .flatten()
+                    .filter_map(|technology| technology.name.as_ref())
+                    .collect(),
+                weight,
+            ),
+            CompanyCriterion::Country => seed
+                .location
+                .as_ref()
+                .and_then(|location| location.country.as_ref())
+                .map(|country| term("location.country", country, weight)),
+        }
+    }
+}
+
+/// How a company is compared with a seed company. Weighted criteria raise
+/// the score of companies that share them; required criteria must match.
+/// A criterion the seed has no data for is left out of the query.
+#[derive(Clone, Debug)]
+pub struct CompanySimilarity {
+    /// Criteria scored, with their boost.
+    pub weights: Vec<(CompanyCriterion, f32)>,
+    /// Criteria every result must share with the seed.
+    pub required: Vec<CompanyCriterion>,
+    /// How many of the weighted criteria a result must share (fewer if the
+    /// seed has data for fewer).
+    pub minimum_should_match: usize,
+    /// Leave the seed itself out of the results.
+    pub exclude_seed: bool,
+    /// Leave these PDL ids out too, e.g. existing customers.
+    pub exclude_ids: Vec<String>,
+}
+
+impl Default for CompanySimilarity {
+    fn default() -> Self {
+        Self {
+            weights: vec![
+                (CompanyCriterion::Industry, 3.0),
+                (CompanyCriterion::Size, 2.0),
+                (CompanyCriterion::Naics, 2.0),
+                (CompanyCriterion::Sic, 1.0),
+                (CompanyCriterion::FundingStage, 1.0),
+                (CompanyCriterion::Technologies, 2.0),
+                (CompanyCriterion::Country, 1.0),
+            ],
+            required: Vec::new(),
+            minimum_should_match: 2,
+            exclude_seed: true,
+            exclude_ids: Vec::new(),
+        }
+    }
+}
+
+impl CompanySimilarity {
+    /// Scores `criterion` with `weight`, replacing its current weight.
+    pub fn weight(mut self, criterion: CompanyCriterion, weight: f32) -> Self {
+        self.weights.retain(|(weighted, _)| *weighted != criterion);
+        self.weights.push((criterion, weight));
+        self
+    }
+
+    /// Stops scoring `criterion`.
+    pub fn ignore(mut self, criterion: CompanyCriterion) -> Self {
+        self.weights.retain(|(weighted, _)| *weighted != criterion);
+        self.required.retain(|required| *required != criterion);
+        self
+    }
+
+    /// Makes `criterion` a filter rather than a score.
+    pub fn require(mut self, criterion: CompanyCriterion) -> Self {
+        if !self.required.contains(&criterion) {
+            self.required.push(criterion);
+        }
+        self
+    }
+
+    /// The Elasticsearch query for companies like `seed`. Fails with
+    /// `ValidationError` when the seed has none of the data compared.
+    pub fn query(&self, seed: &CompanyResponse) -> Result<Value, PDLError> {
+        let must: Vec<Value> = self
+            .required
+            .iter()
+            .filter_map(|criterion| criterion.clause(seed, self.weight_of(criterion)))
+            .collect();
+        let should: Vec<Value> = self
+            .weights
+            .iter()
+            .filter(|(criterion, _)| !self.required.contains(criterion))
+            .filter_map(|(criterion, weight)| criterion.clause(seed, *weight))
+            .collect();
+        if must.is_empty() && should.is_empty() {
+            return Err(PDLError::ValidationError);
+        }
+
+        let mut query = json!({ "bool": {} });
+        if !must.is_empty() {
+            query["bool"]["must"] = json!(must);
+        }
+        if !should.is_empty() {
+            query["bool"]["minimum_should_match"] =
+                json!(self.minimum_should_match.min(should.len()));
+            query["bool"]["should"] = json!(should);
+        }
+        let excluded = exclusions(seed.id.as_ref(), self.exclude_seed, &self.exclude_ids);
+        if !excluded.is_empty() {
+            query["bool"]["must_not"] = json!([{ "terms": { "id": excluded } }]);
+        }
+        Ok(query)
+    }
+
+    /// `query` as `SearchParams` for `Company::search`.
+    pub fn search_params(&self, seed: &CompanyResponse) -> Result<SearchParams, PDLError> {
+        Ok(search_params(self.query(seed)?))
+    }
+
+    /// The weight of `criterion`, or 1 if it is only required.
+    fn weight_of(&self, criterion: &CompanyCriterion) -> f32 {
+        self.weights
+            .iter()
+            .find(|(weighted, _)| weighted == criterion)
+            .map_or(1.0, |(_, weight)| *weight)
+    }
+}
+
+/// The ids to leave out of lookalike results.
+fn exclusions(seed_id: Option<&String>, exclude_seed: bool, exclude_ids: &[String]) -> Vec<String> {
+    seed_id
+        .filter(|_| exclude_seed)
+        .into_iter()
+        .chain(exclude_ids)
+        .cloned()
+        .collect()
+}
+
+fn search_params(query: Value) -> SearchParams {
+    SearchParams {
+        base_params: None,
+        search_base_params: SearchBaseParams {
+            query: Some(query),
+            ..SearchBaseParams::default()
+        },
+        additional_params: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed() -> PersonRecord {
+        serde_json::from_value(json!({
+            "id": "seed",
+            "job_title_role": "engineering",
+            "job_title_levels": ["senior"],
+            "industry": "computer software",
+            "location_geo": "37.77,-122.41",
+            "skills": ["rust", "kubernetes", "postgres"],
+        }))
+        .expect("ERROR")
+    }
+
+    #[test]
+    fn test_person_lookalike_query() {
+        let similarity = PersonSimilarity {
+            location_radius_km: Some(50.0),
+            exclude_ids: vec!["seen".to_string()],
+            ..PersonSimilarity::default()
+        };
+        let params = similarity.search_params(&seed()).expect("ERROR");
+        assert_eq!(
+            params.search_base_params.query,
+            Some(json!({"bool": {
+                "must": [
+                    {"term": {"job_title_role": "engineering"}},
+                    {"terms": {"job_title_levels": ["senior"]}},
+                    {"term": {"industry": "computer software"}},
+                    {"geo_distance": {"distance": "50km", "location_geo": "37.77,-122.41"}},
+                    {"bool": {
+                        "should": [
+                            {"term": {"skills": "rust"}},
+                            {"term": {"skills": "kubernetes"}},
+                            {"term": {"skills": "postgres"}},
+                        ],
+                        "minimum_should_match": 2,
+                    }},
+                ],
+                "must_not": [{"terms": {"id": ["seed", "seen"]}}],
+            }}))
+        );
+        assert!(params.validate().is_ok());
+    }
+
+    fn seed_company() -> CompanyResponse {
+        serde_json::from_value(json!({
+            "id": "seed",
+            "industry": "computer software",
+            "size": "51-200",
+            "naics": [{"naics_code": "513210"}, {"naics_code": null}],
+            "technologies_used": [{"name": "rust"}, {"name": "postgresql"}],
+            "location": {"country": "united states"},
+        }))
+        .expect("ERROR")
+    }
+
+    #[test]
+    fn test_company_lookalike_query() {
+        let similarity = CompanySimilarity::default()
+            .weight(CompanyCriterion::Technologies, 5.0)
+            .ignore(CompanyCriterion::Size)
+            .require(CompanyCriterion::Country);
+        let params = similarity.search_params(&seed_company()).expect("ERROR");
+        assert_eq!(
+            params.search_base_params.query,
+            Some(json!({"bool": {
+                "must": [
+                    {"term": {"location.country": {"value": "united states", "boost": 1.0}}},
+                ],
+                "should": [
+                    {"bool": {"should": [
+                        {"term": {"industry": {"value": "computer software", "boost": 3.0}}},
+                    ]}},
+                    {"terms": {"naics.naics_code": ["513210"], "boost": 2.0}},
+                    {"terms": {"technologies_used.name": ["rust", "postgresql"], "boost": 5.0}},
+                ],
+                "minimum_should_match": 2,
+                "must_not": [{"terms": {"id": ["seed"]}}],
+            }}))
+        );
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_company_lookalike_without_comparable_data() {
+        let seed: CompanyResponse =
+            serde_json::from_value(json!({ "id": "seed", "name": "acme" })).expect("ERROR");
+        assert!(matches!(
+            CompanySimilarity::default().query(&seed),
+            Err(PDLError::ValidationError)
+        ));
+    }
+
+    #[test]
+    fn test_person_lookalike_skips_missing_data() {
+        let seed: PersonRecord =
+            serde_json::from_value(json!({ "industry": "computer software" })).expect("ERROR");
+        let query = PersonSimilarity::default().query(&seed).expect("ERROR");
+        assert_eq!(
+            query,
+            json!({"bool": {"must": [{"term": {"industry": "computer software"}}]}})
+        );
+
+        let nothing: PersonRecord = serde_json::from_value(json!({})).expect("ERROR");
+        assert!(matches!(
+            PersonSimilarity::default().query(&nothing),
+            Err(PDLError::ValidationError)
+        ));
+    }
+}
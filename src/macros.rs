@@ -0,0 +1,68 @@
+/// Generates chainable setters for the params builders. Every builder wraps
+/// the struct it produces in a `params` field; the optional section name
+/// selects the sub-struct of `params` the setters write to.
+///
+/// - `set section { field: Type }` stores `Some(value)` in a plain section.
+/// - `set_opt section { field: Type }` does the same inside an `Option`
+///   section (`base_params`, `additional_params`), creating it on first use.
+/// - `push section { field: Type }` appends to an `Option<Vec<_>>` field, so
+///   multi-valued params can be given more than once.
+///
+/// `set` and `push` without a section write to `params` itself.
+macro_rules! setters {
+    (set { $($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.params.$field = Some($field.into());
+                self
+            }
+        )*
+    };
+    (set $section:ident { $($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.params.$section.$field = Some($field.into());
+                self
+            }
+        )*
+    };
+    (set_opt $section:ident { $($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.params
+                    .$section
+                    .get_or_insert_with(Default::default)
+                    .$field = Some($field.into());
+                self
+            }
+        )*
+    };
+    (push { $($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.params
+                    .$field
+                    .get_or_insert_with(Vec::new)
+                    .push($field.into());
+                self
+            }
+        )*
+    };
+    (push $section:ident { $($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.params
+                    .$section
+                    .$field
+                    .get_or_insert_with(Vec::new)
+                    .push($field.into());
+                self
+            }
+        )*
+    };
+}
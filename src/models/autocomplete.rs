@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{models::common::BaseParams, PDLError};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AutocompleteBaseParams {
     /// Field that autocomplete will be calculated for
     #[serde(rename = "field", default)]
@@ -15,7 +15,7 @@ pub struct AutocompleteBaseParams {
     pub titlecase: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AutocompleteParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -24,6 +24,10 @@ pub struct AutocompleteParams {
 }
 
 impl AutocompleteParams {
+    pub fn builder() -> AutocompleteParamsBuilder {
+        AutocompleteParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.autocomplete_base_params.field.is_empty() {
             return Err(PDLError::ValidationError);
@@ -32,6 +36,34 @@ impl AutocompleteParams {
     }
 }
 
+/// Builder for `AutocompleteParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct AutocompleteParamsBuilder {
+    params: AutocompleteParams,
+}
+
+impl AutocompleteParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+        size: i32,
+    });
+
+    setters!(set autocomplete_base_params {
+        text: String,
+        titlecase: bool,
+    });
+
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.params.autocomplete_base_params.field = field.into();
+        self
+    }
+
+    pub fn build(self) -> Result<AutocompleteParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AutocompleteResponse {
     /// See https://docs.peopledatalabs.com/docs/output-response-autocomplete-api for more information
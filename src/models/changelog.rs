@@ -36,6 +36,10 @@ pub struct ChangelogPersonParams {
 }
 
 impl ChangelogPersonParams {
+    pub fn builder() -> ChangelogPersonParamsBuilder {
+        ChangelogPersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.origin_version.is_none() || self.current_version.is_none() {
             return Err(PDLError::ValidationError);
@@ -47,6 +51,36 @@ impl ChangelogPersonParams {
     }
 }
 
+/// Builder for `ChangelogPersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct ChangelogPersonParamsBuilder {
+    params: ChangelogPersonParams,
+}
+
+impl ChangelogPersonParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+        size: i32,
+    });
+
+    setters!(set {
+        origin_version: String,
+        current_version: String,
+        type_: String,
+        scroll_token: String,
+    });
+
+    setters!(push {
+        ids: String,
+        fields_updated: String,
+    });
+
+    pub fn build(self) -> Result<ChangelogPersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChangelogPersonResponse {
     pub status: Option<i32>,
@@ -1,4 +1,6 @@
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 
 use crate::PDLError;
 
@@ -48,49 +50,65 @@ pub struct BaseParams {
     pub size: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct AdditionalParams {
     /// The minimum likelihood score a response must possess in order to return a 200.
-    #[serde(rename = "min_likelihood", default)]
+    #[serde(
+        rename = "min_likelihood",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
     pub min_likelihood: Option<i32>,
     /// Parameter specifying the fields and data points a response must have to return a 200.
-    #[serde(rename = "required", default)]
+    #[serde(rename = "required", skip_serializing_if = "Option::is_none", default)]
     pub required: Option<String>,
     /// Setting titlecase to true will titlecase the person data in 200 responses.
-    #[serde(rename = "titlecase", default)]
+    #[serde(rename = "titlecase", skip_serializing_if = "Option::is_none", default)]
     pub titlecase: Option<bool>,
     /// A comma-separated string of fields that you would like the response to include.
-    #[serde(rename = "data_include", default)]
+    #[serde(
+        rename = "data_include",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
     pub data_include: Option<String>,
     /// If set to true, includes a top-level (alongside "data", "status", etc) field "matched" which
     /// includes a value for each queried field parameter that was "matched-on" during our internal query.
-    #[serde(rename = "include_if_matched", default)]
+    #[serde(
+        rename = "include_if_matched",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
     pub include_if_matched: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct SearchBaseParams {
     /// An Elasticsearch (v7.7) query.
-    #[serde(rename = "query", default)]
+    #[serde(rename = "query", skip_serializing_if = "Option::is_none", default)]
     pub query: Option<serde_json::Value>,
     /// A SQL query of the format: SELECT * FROM person WHERE XXX.
-    #[serde(rename = "sql", default)]
+    #[serde(rename = "sql", skip_serializing_if = "Option::is_none", default)]
     pub sql: Option<String>,
     /// An offset value for pagination.
-    #[serde(rename = "from", default)]
+    #[serde(rename = "from", skip_serializing_if = "Option::is_none", default)]
     pub from: Option<i32>,
     /// An offset key for paginating between batches.
-    #[serde(rename = "scroll_token", default)]
+    #[serde(
+        rename = "scroll_token",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
     pub scroll_token: Option<String>,
     /// The dataset category to return records from.
-    #[serde(rename = "dataset", default)]
+    #[serde(rename = "dataset", skip_serializing_if = "Option::is_none", default)]
     pub dataset: Option<String>,
     /// Setting titlecase to true will titlecase the person data in 200 responses.
-    #[serde(rename = "titlecase", default)]
+    #[serde(rename = "titlecase", skip_serializing_if = "Option::is_none", default)]
     pub titlecase: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct SearchParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -101,6 +119,10 @@ pub struct SearchParams {
 }
 
 impl SearchParams {
+    pub fn builder() -> SearchParamsBuilder {
+        SearchParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if (self.search_base_params.query.is_none() && self.search_base_params.sql.is_none())
             || (self.search_base_params.query.is_some() && self.search_base_params.sql.is_some())
@@ -111,12 +133,215 @@ impl SearchParams {
     }
 }
 
-pub fn param_serialize<S>(vec: &Option<Vec<String>>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    match vec {
-        Some(data) => serializer.serialize_str(&data.join(", ")),
-        None => serializer.serialize_none(),
+/// Builder for `SearchParams`. `build()` runs `validate()`, so exactly one
+/// of `query` or `sql` must be set.
+#[derive(Debug, Default)]
+pub struct SearchParamsBuilder {
+    params: SearchParams,
+}
+
+impl SearchParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+        size: i32,
+    });
+
+    setters!(set search_base_params {
+        query: serde_json::Value,
+        sql: String,
+        from: i32,
+        scroll_token: String,
+        dataset: String,
+        titlecase: bool,
+    });
+
+    setters!(set_opt additional_params {
+        data_include: String,
+    });
+
+    pub fn build(self) -> Result<SearchParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// How the bulk endpoints treat requests that fail validation before the
+/// batch is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BulkValidation {
+    /// Fail the whole batch if any request is invalid.
+    #[default]
+    RejectBatch,
+    /// Send only the valid requests and report the others in
+    /// `BulkResult::rejected`.
+    DropInvalid,
+}
+
+/// A bulk request item that failed validation and was not sent.
+#[derive(Debug)]
+pub struct RejectedRequest<T> {
+    /// Position of the item in the original `requests`.
+    pub index: usize,
+    pub request: T,
+    pub error: PDLError,
+}
+
+/// The outcome of a validated bulk call.
+#[derive(Debug)]
+pub struct BulkResult<T, R> {
+    /// Responses for the requests that were sent, in order.
+    pub responses: Vec<R>,
+    /// Original position of the request behind each entry of `responses`.
+    pub sent: Vec<usize>,
+    pub rejected: Vec<RejectedRequest<T>>,
+}
+
+impl<T, R> BulkResult<T, R> {
+    /// Validates every request according to `policy`. Returns the requests
+    /// to send along with a result waiting for their responses. `validate`
+    /// may prepare a request in place before it's sent.
+    pub(crate) fn partition<F>(
+        requests: Vec<T>,
+        policy: BulkValidation,
+        mut validate: F,
+    ) -> Result<(Vec<T>, Self), PDLError>
+    where
+        F: FnMut(&mut T) -> Result<(), PDLError>,
+    {
+        let mut valid = Vec::with_capacity(requests.len());
+        let mut result = BulkResult {
+            responses: Vec::new(),
+            sent: Vec::new(),
+            rejected: Vec::new(),
+        };
+
+        for (index, mut request) in requests.into_iter().enumerate() {
+            match validate(&mut request) {
+                Ok(()) => {
+                    valid.push(request);
+                    result.sent.push(index);
+                }
+                Err(error) if policy == BulkValidation::DropInvalid => {
+                    result.rejected.push(RejectedRequest {
+                        index,
+                        request,
+                        error,
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok((valid, result))
+    }
+}
+
+/// The most requests the bulk enrichment endpoints accept in one call.
+pub(crate) const BULK_MAX_REQUESTS: usize = 100;
+
+/// Splits `items` into consecutive batches of at most `size` items.
+pub(crate) fn into_batches<T>(items: Vec<T>, size: usize) -> Vec<Vec<T>> {
+    let mut batches = Vec::with_capacity(items.len().div_ceil(size));
+    let mut items = items.into_iter().peekable();
+    while items.peek().is_some() {
+        batches.push(items.by_ref().take(size).collect());
+    }
+    batches
+}
+
+/// How a multi-valued param is written to a query string. JSON bodies
+/// always carry multi-valued params as arrays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MultiValueEncoding {
+    /// One `key=value` pair per value: `location=a&location=b`.
+    #[default]
+    Repeated,
+    /// A single JSON array: `location=["a","b"]`.
+    JsonArray,
+    /// Values joined with ", ": `location=a, b`. Ambiguous for values that
+    /// themselves contain commas; kept for compatibility.
+    Joined,
+}
+
+/// The `MultiValueEncoding` used for each query-string param, with
+/// per-field overrides keyed by API param name.
+#[derive(Clone, Debug, Default)]
+pub struct QueryEncoding {
+    pub default: MultiValueEncoding,
+    pub fields: HashMap<String, MultiValueEncoding>,
+}
+
+impl QueryEncoding {
+    /// Uses `encoding` for every multi-valued field without an override.
+    pub fn new(default: MultiValueEncoding) -> Self {
+        Self {
+            default,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Overrides the encoding of a single field, e.g. `"location"`.
+    pub fn field(mut self, name: impl Into<String>, encoding: MultiValueEncoding) -> Self {
+        self.fields.insert(name.into(), encoding);
+        self
+    }
+
+    pub fn for_field(&self, name: &str) -> MultiValueEncoding {
+        self.fields.get(name).copied().unwrap_or(self.default)
+    }
+
+    /// Encodes `params` as a query string. Unset params are left out and
+    /// arrays of scalars are written according to their field's encoding;
+    /// nested objects can't be query-encoded and fail with
+    /// `PDLError::SerializationError`.
+    pub fn to_query_string<P>(&self, params: &P) -> Result<String, PDLError>
+    where
+        P: Serialize,
+    {
+        let Value::Object(map) =
+            serde_json::to_value(params).map_err(|_| PDLError::SerializationError)?
+        else {
+            return Err(PDLError::SerializationError);
+        };
+
+        let mut pairs: Vec<(String, String)> = Vec::new();
+        for (key, value) in map {
+            match value {
+                Value::Null => {}
+                Value::Array(values) => {
+                    let values = values
+                        .iter()
+                        .map(scalar_to_string)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or(PDLError::SerializationError)?;
+                    match self.for_field(&key) {
+                        MultiValueEncoding::Repeated => {
+                            pairs.extend(values.into_iter().map(|v| (key.clone(), v)))
+                        }
+                        MultiValueEncoding::JsonArray => pairs.push((
+                            key,
+                            serde_json::to_string(&values)
+                                .map_err(|_| PDLError::SerializationError)?,
+                        )),
+                        MultiValueEncoding::Joined => pairs.push((key, values.join(", "))),
+                    }
+                }
+                value => pairs.push((
+                    key,
+                    scalar_to_string(&value).ok_or(PDLError::SerializationError)?,
+                )),
+            }
+        }
+
+        serde_urlencoded::to_string(pairs).map_err(|_| PDLError::SerializationError)
+    }
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
     }
 }
@@ -5,10 +5,11 @@ use serde_json::Value;
 
 use crate::{
     models::common::{AdditionalParams, BaseParams},
+    normalize::{parse_address, profile::canonical_company_profile},
     PDLError,
 };
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct CompanyParams {
     /// The PDL ID of the company
     #[serde(rename = "pdl_id", skip_serializing_if = "Option::is_none", default)]
@@ -46,6 +47,38 @@ pub struct CompanyParams {
 }
 
 impl CompanyParams {
+    pub fn builder() -> CompanyParamsBuilder {
+        CompanyParamsBuilder::default()
+    }
+
+    /// Fills `street_address`, `locality`, `region`, `country` and
+    /// `postal_code` from a one-line address, and adds the address itself to
+    /// `location` as a fallback. Fields the address doesn't mention are
+    /// left as they are.
+    pub fn fill_address(&mut self, address: &str) -> Result<(), PDLError> {
+        let parsed = parse_address(address)?;
+        self.street_address = parsed.street_address.or(self.street_address.take());
+        self.locality = parsed.locality.or(self.locality.take());
+        self.region = parsed.region.or(self.region.take());
+        self.country = parsed.country.or(self.country.take());
+        self.postal_code = parsed.postal_code.or(self.postal_code.take());
+        let address = address.trim().to_string();
+        let location = self.location.get_or_insert_with(Vec::new);
+        if !location.contains(&address) {
+            location.push(address);
+        }
+        Ok(())
+    }
+
+    /// Rewrites `profile` in the form PDL expects. Fails if it isn't a
+    /// company's social profile URL, leaving it unchanged.
+    pub fn canonicalize_profile(&mut self) -> Result<(), PDLError> {
+        if let Some(profile) = &self.profile {
+            self.profile = Some(canonical_company_profile(profile)?);
+        }
+        Ok(())
+    }
+
     // Validation function
     pub fn validate(&self) -> Result<(), PDLError> {
         // Check if at least one field is present
@@ -68,7 +101,35 @@ impl CompanyParams {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Builder for `CompanyParams`, mostly useful for bulk requests.
+#[derive(Debug, Default)]
+pub struct CompanyParamsBuilder {
+    params: CompanyParams,
+}
+
+impl CompanyParamsBuilder {
+    setters!(set {
+        pdl_id: String,
+        name: String,
+        website: String,
+        profile: String,
+        ticker: String,
+        locality: String,
+        region: String,
+        country: String,
+        street_address: String,
+        postal_code: String,
+    });
+
+    setters!(push { location: String });
+
+    pub fn build(self) -> Result<CompanyParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct EnrichCompanyParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -81,6 +142,10 @@ pub struct EnrichCompanyParams {
 }
 
 impl EnrichCompanyParams {
+    pub fn builder() -> EnrichCompanyParamsBuilder {
+        EnrichCompanyParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.company_params.pdl_id.is_none()
             && self.company_params.name.is_none()
@@ -94,12 +159,66 @@ impl EnrichCompanyParams {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Builder for `EnrichCompanyParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct EnrichCompanyParamsBuilder {
+    params: EnrichCompanyParams,
+}
+
+impl EnrichCompanyParamsBuilder {
+    setters!(set company_params {
+        pdl_id: String,
+        name: String,
+        website: String,
+        profile: String,
+        ticker: String,
+        locality: String,
+        region: String,
+        country: String,
+        street_address: String,
+        postal_code: String,
+    });
+
+    setters!(push company_params {
+        location: String,
+    });
+
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set_opt additional_params {
+        min_likelihood: i32,
+        required: String,
+        titlecase: bool,
+        data_include: String,
+        include_if_matched: bool,
+    });
+
+    pub fn build(self) -> Result<EnrichCompanyParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+/// Requests beyond the API's 100-per-call limit are split into several calls
+/// by `Company::bulk_enrich`, each sharing the top-level options.
+#[derive(Debug, Deserialize, Serialize, Default)]
 pub struct BulkEnrichCompanyParams {
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub base_params: Option<BaseParams>,
+
     pub requests: Vec<BulkEnrichSingleCompanyParams>,
+
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub additional_params: Option<AdditionalParams>,
 }
 
 impl BulkEnrichCompanyParams {
+    pub fn builder() -> BulkEnrichCompanyParamsBuilder {
+        BulkEnrichCompanyParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         for request in &self.requests {
             request.validate()?
@@ -108,11 +227,60 @@ impl BulkEnrichCompanyParams {
     }
 }
 
+/// Builder for `BulkEnrichCompanyParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct BulkEnrichCompanyParamsBuilder {
+    params: BulkEnrichCompanyParams,
+}
+
+impl BulkEnrichCompanyParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set_opt additional_params {
+        min_likelihood: i32,
+        required: String,
+        titlecase: bool,
+        data_include: String,
+    });
+
+    pub fn request(mut self, params: CompanyParams) -> Self {
+        self.params.requests.push(BulkEnrichSingleCompanyParams {
+            params,
+            metadata: None,
+        });
+        self
+    }
+
+    pub fn request_with_metadata(
+        mut self,
+        params: CompanyParams,
+        metadata: CompanyMetadata,
+    ) -> Self {
+        self.params.requests.push(BulkEnrichSingleCompanyParams {
+            params,
+            metadata: Some(metadata),
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<BulkEnrichCompanyParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BulkEnrichSingleCompanyParams {
     pub params: CompanyParams,
+    /// Echoed back unchanged in the matching `BulkCompanyEnrichResponse`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub metadata: Option<CompanyMetadata>,
 }
 
+pub type CompanyMetadata = HashMap<String, String>;
+
 impl BulkEnrichSingleCompanyParams {
     pub fn validate(&self) -> Result<(), PDLError> {
         self.params.validate()?;
@@ -134,6 +302,19 @@ pub struct CleanCompanyParams {
 }
 
 impl CleanCompanyParams {
+    pub fn builder() -> CleanCompanyParamsBuilder {
+        CleanCompanyParamsBuilder::default()
+    }
+
+    /// Rewrites `profile` in the form PDL expects. Fails if it isn't a
+    /// company's social profile URL, leaving it unchanged.
+    pub fn canonicalize_profile(&mut self) -> Result<(), PDLError> {
+        if let Some(profile) = &self.profile {
+            self.profile = Some(canonical_company_profile(profile)?);
+        }
+        Ok(())
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.name.is_none() && self.website.is_none() && self.profile.is_none() {
             return Err(PDLError::ValidationError);
@@ -142,6 +323,25 @@ impl CleanCompanyParams {
     }
 }
 
+/// Builder for `CleanCompanyParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct CleanCompanyParamsBuilder {
+    params: CleanCompanyParams,
+}
+
+impl CleanCompanyParamsBuilder {
+    setters!(set {
+        name: String,
+        website: String,
+        profile: String,
+    });
+
+    pub fn build(self) -> Result<CleanCompanyParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchCompanyResponse {
     pub status: i32,
@@ -398,6 +598,7 @@ pub struct BulkCompanyEnrichResponse {
     pub data: Option<Vec<CompanyResponse>>,
     pub status: i32,
     pub likelihood: Option<i32>,
+    pub metadata: Option<CompanyMetadata>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -422,3 +623,31 @@ pub struct CleanCompanyResponse {
     pub score: f32,
     pub fuzzy_match: bool,
 }
+
+/// Confidence in a cleaned company is its `score`, scaled down by this
+/// when the cleaner only found a fuzzy match.
+pub(crate) const FUZZY_MATCH_WEIGHT: f32 = 0.5;
+
+impl CleanCompanyResponse {
+    /// How far to trust the cleaned company: `score`, halved for fuzzy
+    /// matches.
+    pub fn confidence(&self) -> f32 {
+        if self.fuzzy_match {
+            self.score * FUZZY_MATCH_WEIGHT
+        } else {
+            self.score
+        }
+    }
+}
+
+/// The result of `Company::clean_and_enrich`.
+#[derive(Debug)]
+pub struct CleanEnrichCompanyResponse {
+    /// `None` when the cleaner found no company.
+    pub clean: Option<CleanCompanyResponse>,
+    /// `None` when enrichment found no company.
+    pub enrich: Option<CompanyResponse>,
+    /// `clean.confidence()`, or 0 when the cleaner found nothing and the raw
+    /// input was enriched instead.
+    pub confidence: f32,
+}
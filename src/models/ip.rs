@@ -34,7 +34,7 @@ pub struct IPBaseParams {
     pub min_confidence: Option<ConfidenceLevel>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct IPParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -44,6 +44,10 @@ pub struct IPParams {
 }
 
 impl IPParams {
+    pub fn builder() -> IPParamsBuilder {
+        IPParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.ip_base_params.ip.is_none() {
             return Err(PDLError::ValidationError);
@@ -52,6 +56,32 @@ impl IPParams {
     }
 }
 
+/// Builder for `IPParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct IPParamsBuilder {
+    params: IPParams,
+}
+
+impl IPParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set ip_base_params {
+        ip: String,
+        return_ip_location: bool,
+        return_ip_metadata: bool,
+        return_person: bool,
+        return_if_unmatched: bool,
+        min_confidence: ConfidenceLevel,
+    });
+
+    pub fn build(self) -> Result<IPParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IPResponse {
     pub status: i32,
@@ -97,7 +97,7 @@ pub struct JobPostingSearchBaseParams {
     pub is_active: Option<bool>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct JobPostingSearchParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -106,6 +106,10 @@ pub struct JobPostingSearchParams {
 }
 
 impl JobPostingSearchParams {
+    pub fn builder() -> JobPostingSearchParamsBuilder {
+        JobPostingSearchParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if let Some(base) = &self.base_params {
             if let Some(size) = base.size {
@@ -118,6 +122,56 @@ impl JobPostingSearchParams {
     }
 }
 
+/// Builder for `JobPostingSearchParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct JobPostingSearchParamsBuilder {
+    params: JobPostingSearchParams,
+}
+
+impl JobPostingSearchParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+        size: i32,
+    });
+
+    setters!(set search_base_params {
+        query: serde_json::Value,
+        scroll_token: String,
+        id: String,
+        first_seen_min: String,
+        first_seen_max: String,
+        deactivated_date_min: String,
+        deactivated_date_max: String,
+        title: String,
+        title_class: String,
+        title_role: String,
+        title_sub_role: String,
+        title_levels: String,
+        company_id: String,
+        company_name: String,
+        company_industry: String,
+        company_industry_v2: String,
+        company_website: String,
+        company_profile: String,
+        location: String,
+        description: String,
+        salary_range_min: i32,
+        salary_range_max: i32,
+        salary_currency: String,
+        salary_period: SalaryPeriod,
+        remote_work_policy: RemoteWorkPolicy,
+        inferred_skills: String,
+        last_verified_min: String,
+        last_verified_max: String,
+        is_active: bool,
+    });
+
+    pub fn build(self) -> Result<JobPostingSearchParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JobPosting {
     pub id: Option<String>,
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{models::common::BaseParams, PDLError};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct JobTitleBaseParams {
     /// JobTitle that is used as the seed for enrichment
     #[serde(rename = "job_title", default)]
@@ -12,7 +12,7 @@ pub struct JobTitleBaseParams {
     pub titlecase: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct JobTitleParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -22,6 +22,10 @@ pub struct JobTitleParams {
 }
 
 impl JobTitleParams {
+    pub fn builder() -> JobTitleParamsBuilder {
+        JobTitleParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.job_title_base_params.job_title.is_none() {
             return Err(PDLError::ValidationError);
@@ -30,6 +34,28 @@ impl JobTitleParams {
     }
 }
 
+/// Builder for `JobTitleParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct JobTitleParamsBuilder {
+    params: JobTitleParams,
+}
+
+impl JobTitleParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set job_title_base_params {
+        job_title: String,
+        titlecase: bool,
+    });
+
+    pub fn build(self) -> Result<JobTitleParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JobTitleResponse {
     pub status: i32,
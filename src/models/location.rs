@@ -5,14 +5,14 @@ use crate::{
     PDLError,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct LocationParams {
     /// The raw location to process
     #[serde(rename = "location", default)]
     pub location: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct CleanLocationParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -25,6 +25,10 @@ pub struct CleanLocationParams {
 }
 
 impl CleanLocationParams {
+    pub fn builder() -> CleanLocationParamsBuilder {
+        CleanLocationParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.location_params.location.is_none() {
             return Err(PDLError::ValidationError);
@@ -33,6 +37,27 @@ impl CleanLocationParams {
     }
 }
 
+/// Builder for `CleanLocationParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct CleanLocationParamsBuilder {
+    params: CleanLocationParams,
+}
+
+impl CleanLocationParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set location_params {
+        location: String,
+    });
+
+    pub fn build(self) -> Result<CleanLocationParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CleanLocationResponse {
     /// See https://docs.peopledatalabs.com/docs/output-response-cleaner-apis#location-cleaner-api-response for more information
@@ -9,18 +9,28 @@ pub mod location;
 pub mod person;
 pub mod school;
 
-pub use autocomplete::{AutocompleteBaseParams, AutocompleteParams};
-pub use common::{BaseParams, SearchBaseParams, SearchParams};
-pub use company::{CleanCompanyParams, CompanyParams, EnrichCompanyParams};
-pub use ip::{IPBaseParams, IPParams};
+pub use autocomplete::{AutocompleteBaseParams, AutocompleteParams, AutocompleteParamsBuilder};
+pub use common::{
+    AdditionalParams, BaseParams, BulkResult, BulkValidation, MultiValueEncoding, QueryEncoding,
+    RejectedRequest, SearchBaseParams, SearchParams, SearchParamsBuilder,
+};
+pub use company::{
+    BulkEnrichCompanyParams, BulkEnrichCompanyParamsBuilder, BulkEnrichSingleCompanyParams,
+    CleanCompanyParams, CleanCompanyParamsBuilder, CompanyMetadata, CompanyParams,
+    CompanyParamsBuilder, EnrichCompanyParams, EnrichCompanyParamsBuilder,
+};
+pub use ip::{ConfidenceLevel, IPBaseParams, IPParams, IPParamsBuilder};
 pub use jobposting::{
-    JobPostingSearchBaseParams, JobPostingSearchParams, RemoteWorkPolicy, SalaryPeriod,
+    JobPostingSearchBaseParams, JobPostingSearchParams, JobPostingSearchParamsBuilder,
+    RemoteWorkPolicy, SalaryPeriod,
 };
-pub use jobtitle::{JobTitleBaseParams, JobTitleParams};
-pub use location::{CleanLocationParams, LocationParams};
+pub use jobtitle::{JobTitleBaseParams, JobTitleParams, JobTitleParamsBuilder};
+pub use location::{CleanLocationParams, CleanLocationParamsBuilder, LocationParams};
 pub use person::{
-    BulkEnrichPersonParams, BulkEnrichSinglePersonParams, BulkRetrievePersonParams,
-    BulkRetrieveSinglePersonParams, EnrichPersonParams, IdentifyPersonParams, PersonMetadata,
-    PersonParams, RetrievePersonParams,
+    BulkEnrichPersonParams, BulkEnrichPersonParamsBuilder, BulkEnrichSinglePersonParams,
+    BulkRetrievePersonParams, BulkRetrievePersonParamsBuilder, BulkRetrieveSinglePersonParams,
+    EnrichPersonParams, EnrichPersonParamsBuilder, IdentifyPersonParams,
+    IdentifyPersonParamsBuilder, PersonField, PersonInputRule, PersonMetadata, PersonParams,
+    PersonParamsBuilder, RetrievePersonParams, RetrievePersonParamsBuilder, PERSON_INPUT_RULES,
 };
-pub use school::{CleanSchoolParams, SchoolParams};
+pub use school::{CleanSchoolParams, CleanSchoolParamsBuilder, SchoolParams};
@@ -1,59 +1,40 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::fmt;
 
 use crate::{
-    models::common::{param_serialize, AdditionalParams, BaseParams},
-    PDLError,
+    canonicalize_profile,
+    models::common::{AdditionalParams, BaseParams, QueryEncoding},
+    normalize_phone, parse_address, parse_name, EmailHashing, PDLError, ProfileKind,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct PersonParams {
-    #[serde(
-        rename = "pdl_id",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "pdl_id", skip_serializing_if = "Option::is_none", default)]
     pub pdl_id: Option<Vec<String>>,
 
-    #[serde(
-        rename = "name",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none", default)]
     pub name: Option<Vec<String>>,
 
     #[serde(
         rename = "first_name",
-        serialize_with = "param_serialize",
         skip_serializing_if = "Option::is_none",
         default
     )]
     pub first_name: Option<Vec<String>>,
 
-    #[serde(
-        rename = "last_name",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "last_name", skip_serializing_if = "Option::is_none", default)]
     pub last_name: Option<Vec<String>>,
 
     #[serde(
         rename = "middle_name",
-        serialize_with = "param_serialize",
         skip_serializing_if = "Option::is_none",
         default
     )]
     pub middle_name: Option<Vec<String>>,
 
-    #[serde(
-        rename = "location",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "location", skip_serializing_if = "Option::is_none", default)]
     pub location: Option<Vec<String>>,
 
     #[serde(
@@ -74,110 +55,412 @@ pub struct PersonParams {
 
     #[serde(
         rename = "postal_code",
-        serialize_with = "param_serialize",
         skip_serializing_if = "Option::is_none",
         default
     )]
     pub postal_code: Option<Vec<String>>,
 
-    #[serde(
-        rename = "company",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "company", skip_serializing_if = "Option::is_none", default)]
     pub company: Option<Vec<String>>,
 
-    #[serde(
-        rename = "school",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "school", skip_serializing_if = "Option::is_none", default)]
     pub school: Option<Vec<String>>,
 
-    #[serde(
-        rename = "phone",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "phone", skip_serializing_if = "Option::is_none", default)]
     pub phone: Option<Vec<String>>,
 
-    #[serde(
-        rename = "email",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "email", skip_serializing_if = "Option::is_none", default)]
     pub email: Option<Vec<String>>,
 
     #[serde(
         rename = "email_hash",
-        serialize_with = "param_serialize",
         skip_serializing_if = "Option::is_none",
         default
     )]
     pub email_hash: Option<Vec<String>>,
 
-    #[serde(
-        rename = "profile",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "profile", skip_serializing_if = "Option::is_none", default)]
     pub profile: Option<Vec<String>>,
 
-    #[serde(
-        rename = "lid",
-        serialize_with = "param_serialize",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(rename = "lid", skip_serializing_if = "Option::is_none", default)]
     pub lid: Option<Vec<String>>,
 
     #[serde(
         rename = "birth_date",
-        serialize_with = "param_serialize",
         skip_serializing_if = "Option::is_none",
         default
     )]
     pub birth_date: Option<Vec<String>>,
+
+    /// How the multi-valued fields above are written when the params are
+    /// sent as a query string. Not sent to the API.
+    #[serde(skip)]
+    pub query_encoding: QueryEncoding,
 }
 
 impl PersonParams {
-    fn validate(&self) -> Result<(), PDLError> {
-        if self.pdl_id.is_some() {
+    pub fn builder() -> PersonParamsBuilder {
+        PersonParamsBuilder::default()
+    }
+
+    /// Checks the input against `PERSON_INPUT_RULES`. When no rule is
+    /// satisfied the error lists every combination that would be, closest
+    /// to the given input first.
+    pub fn validate(&self) -> Result<(), PDLError> {
+        if PERSON_INPUT_RULES
+            .iter()
+            .any(|rule| rule.is_satisfied_by(self))
+        {
             return Ok(());
         }
-        if self.profile.is_some() {
+
+        let mut rules: Vec<&PersonInputRule> = PERSON_INPUT_RULES.iter().collect();
+        rules.sort_by_key(|rule| {
+            let missing = rule.missing(self).len();
+            (missing, Reverse(rule.groups.len() - missing))
+        });
+        Err(PDLError::InsufficientInputError(
+            rules.iter().map(|rule| rule.to_string()).collect(),
+        ))
+    }
+
+    /// Normalizes and hashes `email` into `email_hash`.
+    pub fn add_email_hash(&mut self, email: &str, hashing: EmailHashing) -> Result<(), PDLError> {
+        let hash = hashing.hash(email)?;
+        self.email_hash.get_or_insert_with(Vec::new).push(hash);
+        Ok(())
+    }
+
+    /// Rewrites every `phone` in E.164, reading numbers without a country
+    /// code as national numbers of `default_region` (e.g. `"US"`).
+    /// Duplicates are dropped; nothing is changed if any phone is invalid.
+    pub fn normalize_phones(&mut self, default_region: &str) -> Result<(), PDLError> {
+        let Some(phones) = &self.phone else {
             return Ok(());
+        };
+        let mut normalized: Vec<String> = Vec::with_capacity(phones.len());
+        for phone in phones {
+            let phone = normalize_phone(phone, default_region)?;
+            if !normalized.contains(&phone) {
+                normalized.push(phone);
+            }
         }
-        if self.email.is_some() {
+
+        self.phone = Some(normalized);
+        Ok(())
+    }
+
+    /// Splits every full `name` into `first_name`, `middle_name` and
+    /// `last_name`, dropping honorifics and suffixes, so matching uses the
+    /// structured name fields. Single-word names stay in `name`. Nothing is
+    /// changed if any name can't be parsed.
+    pub fn split_names(&mut self) -> Result<(), PDLError> {
+        let Some(names) = &self.name else {
             return Ok(());
+        };
+        let parsed = names
+            .iter()
+            .map(|name| parse_name(name).map(|parsed| (name.clone(), parsed)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut unsplit: Vec<String> = Vec::new();
+        for (name, parsed) in parsed {
+            let (Some(first), Some(last)) = (parsed.first, parsed.last) else {
+                unsplit.push(name);
+                continue;
+            };
+            push_unique(&mut self.first_name, first);
+            if let Some(middle) = parsed.middle {
+                push_unique(&mut self.middle_name, middle);
+            }
+            push_unique(&mut self.last_name, last);
         }
-        if self.phone.is_some() {
-            return Ok(());
+        self.name = (!unsplit.is_empty()).then_some(unsplit);
+        Ok(())
+    }
+
+    /// Fills `street_address`, `locality`, `region`, `country` and
+    /// `postal_code` from a one-line address, and adds the address itself to
+    /// `location` as a fallback. Fields the address doesn't mention are
+    /// left as they are.
+    pub fn fill_address(&mut self, address: &str) -> Result<(), PDLError> {
+        let parsed = parse_address(address)?;
+        self.street_address = parsed.street_address.or(self.street_address.take());
+        self.locality = parsed.locality.or(self.locality.take());
+        self.region = parsed.region.or(self.region.take());
+        self.country = parsed.country.or(self.country.take());
+        if let Some(postal_code) = parsed.postal_code {
+            push_unique(&mut self.postal_code, postal_code);
         }
-        if self.email_hash.is_some() {
+        push_unique(&mut self.location, address.trim().to_string());
+        Ok(())
+    }
+
+    /// Rewrites every `profile` URL in the form PDL expects, moving LinkedIn
+    /// profiles only known by numeric id into `lid`. Duplicates are dropped;
+    /// nothing is changed if any URL isn't a person's social profile.
+    pub fn canonicalize_profiles(&mut self) -> Result<(), PDLError> {
+        let Some(urls) = &self.profile else {
             return Ok(());
+        };
+        let mut profiles: Vec<String> = Vec::with_capacity(urls.len());
+        let mut lids: Vec<String> = Vec::new();
+        for url in urls {
+            let profile = canonicalize_profile(url)?;
+            if matches!(
+                profile.kind,
+                Some(ProfileKind::Company | ProfileKind::School)
+            ) {
+                return Err(PDLError::ValidationError);
+            }
+            let (values, value) = match profile.url() {
+                Some(url) => (&mut profiles, url),
+                None => (&mut lids, profile.numeric_id.unwrap_or_default()),
+            };
+            if !values.contains(&value) {
+                values.push(value);
+            }
         }
-        if self.lid.is_none() {
-            return Ok(());
+
+        self.profile = (!profiles.is_empty()).then_some(profiles);
+        if !lids.is_empty() {
+            let lid = self.lid.get_or_insert_with(Vec::new);
+            lids.retain(|id| !lid.contains(id));
+            lid.extend(lids);
         }
+        Ok(())
+    }
 
-        if ((self.first_name.is_some() && self.last_name.is_some()) || self.name.is_some())
-            && (self.locality.is_some()
-                || self.region.is_some()
-                || self.company.is_some()
-                || self.school.is_none()
-                || self.location.is_none()
-                || self.postal_code.is_none())
-        {
+    /// Replaces every `email` with its hash in `email_hash`, so no plaintext
+    /// email is sent. Nothing is changed if any email is malformed.
+    pub fn hash_emails(&mut self, hashing: EmailHashing) -> Result<(), PDLError> {
+        let Some(emails) = &self.email else {
             return Ok(());
+        };
+        let hashes = emails
+            .iter()
+            .map(|email| hashing.hash(email))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.email = None;
+        self.email_hash.get_or_insert_with(Vec::new).extend(hashes);
+        Ok(())
+    }
+}
+
+fn push_unique(values: &mut Option<Vec<String>>, value: String) {
+    let values = values.get_or_insert_with(Vec::new);
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+/// A `PersonParams` input that counts toward PDL's matching minimums.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PersonField {
+    PdlId,
+    Name,
+    FirstName,
+    LastName,
+    Location,
+    StreetAddress,
+    Locality,
+    Region,
+    PostalCode,
+    Company,
+    School,
+    Phone,
+    Email,
+    EmailHash,
+    Profile,
+    Lid,
+}
+
+impl PersonField {
+    /// The API parameter name of the field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PersonField::PdlId => "pdl_id",
+            PersonField::Name => "name",
+            PersonField::FirstName => "first_name",
+            PersonField::LastName => "last_name",
+            PersonField::Location => "location",
+            PersonField::StreetAddress => "street_address",
+            PersonField::Locality => "locality",
+            PersonField::Region => "region",
+            PersonField::PostalCode => "postal_code",
+            PersonField::Company => "company",
+            PersonField::School => "school",
+            PersonField::Phone => "phone",
+            PersonField::Email => "email",
+            PersonField::EmailHash => "email_hash",
+            PersonField::Profile => "profile",
+            PersonField::Lid => "lid",
+        }
+    }
+
+    /// Whether the field holds at least one non-blank value.
+    pub fn is_set(&self, params: &PersonParams) -> bool {
+        fn any(values: &Option<Vec<String>>) -> bool {
+            values
+                .as_ref()
+                .is_some_and(|v| v.iter().any(|s| !s.trim().is_empty()))
+        }
+        fn one(value: &Option<String>) -> bool {
+            value.as_ref().is_some_and(|s| !s.trim().is_empty())
+        }
+
+        match self {
+            PersonField::PdlId => any(&params.pdl_id),
+            PersonField::Name => any(&params.name),
+            PersonField::FirstName => any(&params.first_name),
+            PersonField::LastName => any(&params.last_name),
+            PersonField::Location => any(&params.location),
+            PersonField::StreetAddress => one(&params.street_address),
+            PersonField::Locality => one(&params.locality),
+            PersonField::Region => one(&params.region),
+            PersonField::PostalCode => any(&params.postal_code),
+            PersonField::Company => any(&params.company),
+            PersonField::School => any(&params.school),
+            PersonField::Phone => any(&params.phone),
+            PersonField::Email => any(&params.email),
+            PersonField::EmailHash => any(&params.email_hash),
+            PersonField::Profile => any(&params.profile),
+            PersonField::Lid => any(&params.lid),
         }
+    }
+}
 
-        Err(PDLError::ValidationError)
+/// One combination of inputs that is enough for PDL to match a person. The
+/// rule is satisfied when every group has at least one of its fields set.
+#[derive(Debug)]
+pub struct PersonInputRule {
+    pub groups: &'static [&'static [PersonField]],
+}
+
+impl PersonInputRule {
+    pub fn is_satisfied_by(&self, params: &PersonParams) -> bool {
+        self.missing(params).is_empty()
+    }
+
+    /// The groups that still need one of their fields set.
+    pub fn missing(&self, params: &PersonParams) -> Vec<&'static [PersonField]> {
+        self.groups
+            .iter()
+            .filter(|group| !group.iter().any(|field| field.is_set(params)))
+            .copied()
+            .collect()
+    }
+}
+
+impl fmt::Display for PersonInputRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| match group {
+                [field] => field.as_str().to_string(),
+                fields => format!(
+                    "one of ({})",
+                    fields
+                        .iter()
+                        .map(PersonField::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })
+            .collect();
+        f.write_str(&groups.join(" + "))
+    }
+}
+
+/// Name inputs only match when paired with one of these.
+static NAME_CONTEXT: &[PersonField] = &[
+    PersonField::Company,
+    PersonField::School,
+    PersonField::Location,
+    PersonField::StreetAddress,
+    PersonField::Locality,
+    PersonField::Region,
+    PersonField::PostalCode,
+];
+
+/// PDL's documented minimum input for person enrichment, shared by enrich,
+/// identify and bulk enrich.
+/// docs: https://docs.peopledatalabs.com/docs/input-parameters-person-enrichment-api
+pub static PERSON_INPUT_RULES: &[PersonInputRule] = &[
+    PersonInputRule {
+        groups: &[&[PersonField::PdlId]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::Profile]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::Email]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::Phone]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::EmailHash]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::Lid]],
+    },
+    PersonInputRule {
+        groups: &[&[PersonField::Name], NAME_CONTEXT],
+    },
+    PersonInputRule {
+        groups: &[
+            &[PersonField::FirstName],
+            &[PersonField::LastName],
+            NAME_CONTEXT,
+        ],
+    },
+];
+
+/// Builder for `PersonParams`, mostly useful for bulk requests. Multi-valued
+/// setters append, so `.email(a).email(b)` sends both addresses.
+#[derive(Debug, Default)]
+pub struct PersonParamsBuilder {
+    params: PersonParams,
+}
+
+impl PersonParamsBuilder {
+    setters!(push {
+        pdl_id: String,
+        name: String,
+        first_name: String,
+        last_name: String,
+        middle_name: String,
+        location: String,
+        postal_code: String,
+        company: String,
+        school: String,
+        phone: String,
+        email: String,
+        email_hash: String,
+        profile: String,
+        lid: String,
+        birth_date: String,
+    });
+
+    setters!(set {
+        street_address: String,
+        locality: String,
+        region: String,
+        country: String,
+    });
+
+    /// How multi-valued fields are written when sent as a query string.
+    pub fn query_encoding(mut self, query_encoding: QueryEncoding) -> Self {
+        self.params.query_encoding = query_encoding;
+        self
+    }
+
+    pub fn build(self) -> Result<PersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
     }
 }
 
@@ -194,11 +477,71 @@ pub struct EnrichPersonParams {
 }
 
 impl EnrichPersonParams {
+    pub fn builder() -> EnrichPersonParamsBuilder {
+        EnrichPersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         self.person_params.validate()
     }
 }
 
+/// Builder for `EnrichPersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct EnrichPersonParamsBuilder {
+    params: EnrichPersonParams,
+}
+
+impl EnrichPersonParamsBuilder {
+    setters!(push person_params {
+        pdl_id: String,
+        name: String,
+        first_name: String,
+        last_name: String,
+        middle_name: String,
+        location: String,
+        postal_code: String,
+        company: String,
+        school: String,
+        phone: String,
+        email: String,
+        email_hash: String,
+        profile: String,
+        lid: String,
+        birth_date: String,
+    });
+
+    setters!(set person_params {
+        street_address: String,
+        locality: String,
+        region: String,
+        country: String,
+    });
+
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set_opt additional_params {
+        min_likelihood: i32,
+        required: String,
+        titlecase: bool,
+        data_include: String,
+        include_if_matched: bool,
+    });
+
+    /// How multi-valued fields are written when sent as a query string.
+    pub fn query_encoding(mut self, query_encoding: QueryEncoding) -> Self {
+        self.params.person_params.query_encoding = query_encoding;
+        self
+    }
+
+    pub fn build(self) -> Result<EnrichPersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EnrichPersonResponse {
     pub status: i32,
@@ -206,7 +549,7 @@ pub struct EnrichPersonResponse {
     pub data: Person,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct BulkEnrichPersonParams {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub requires: Option<String>,
@@ -214,6 +557,10 @@ pub struct BulkEnrichPersonParams {
 }
 
 impl BulkEnrichPersonParams {
+    pub fn builder() -> BulkEnrichPersonParamsBuilder {
+        BulkEnrichPersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         for request in &self.requests {
             request.validate()?
@@ -222,6 +569,37 @@ impl BulkEnrichPersonParams {
     }
 }
 
+/// Builder for `BulkEnrichPersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct BulkEnrichPersonParamsBuilder {
+    params: BulkEnrichPersonParams,
+}
+
+impl BulkEnrichPersonParamsBuilder {
+    setters!(set { requires: String });
+
+    pub fn request(mut self, params: PersonParams) -> Self {
+        self.params.requests.push(BulkEnrichSinglePersonParams {
+            params,
+            metadata: None,
+        });
+        self
+    }
+
+    pub fn request_with_metadata(mut self, params: PersonParams, metadata: PersonMetadata) -> Self {
+        self.params.requests.push(BulkEnrichSinglePersonParams {
+            params,
+            metadata: Some(metadata),
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<BulkEnrichPersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BulkEnrichSinglePersonParams {
     pub params: PersonParams,
@@ -238,8 +616,7 @@ impl BulkEnrichSinglePersonParams {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BulkEnrichPersonResponse {
-    #[serde(flatten)]
-    pub data: Option<Vec<EnrichPersonResponse>>,
+    pub data: Option<Person>,
     pub status: i32,
     pub likelihood: Option<i32>,
     pub metadata: Option<PersonMetadata>,
@@ -258,12 +635,72 @@ pub struct IdentifyPersonParams {
 }
 
 impl IdentifyPersonParams {
+    pub fn builder() -> IdentifyPersonParamsBuilder {
+        IdentifyPersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         self.person_params.validate()?;
         Ok(())
     }
 }
 
+/// Builder for `IdentifyPersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct IdentifyPersonParamsBuilder {
+    params: IdentifyPersonParams,
+}
+
+impl IdentifyPersonParamsBuilder {
+    setters!(push person_params {
+        pdl_id: String,
+        name: String,
+        first_name: String,
+        last_name: String,
+        middle_name: String,
+        location: String,
+        postal_code: String,
+        company: String,
+        school: String,
+        phone: String,
+        email: String,
+        email_hash: String,
+        profile: String,
+        lid: String,
+        birth_date: String,
+    });
+
+    setters!(set person_params {
+        street_address: String,
+        locality: String,
+        region: String,
+        country: String,
+    });
+
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set_opt additional_params {
+        min_likelihood: i32,
+        required: String,
+        titlecase: bool,
+        data_include: String,
+        include_if_matched: bool,
+    });
+
+    /// How multi-valued fields are written when sent as a query string.
+    pub fn query_encoding(mut self, query_encoding: QueryEncoding) -> Self {
+        self.params.person_params.query_encoding = query_encoding;
+        self
+    }
+
+    pub fn build(self) -> Result<IdentifyPersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IdentifyPersonResponse {
     pub status: i32,
@@ -277,7 +714,7 @@ pub struct PersonMatch {
     pub matched_on: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct RetrievePersonParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -287,6 +724,10 @@ pub struct RetrievePersonParams {
 }
 
 impl RetrievePersonParams {
+    pub fn builder() -> RetrievePersonParamsBuilder {
+        RetrievePersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.person_id.is_empty() {
             return Err(PDLError::ValidationError);
@@ -295,6 +736,28 @@ impl RetrievePersonParams {
     }
 }
 
+/// Builder for `RetrievePersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct RetrievePersonParamsBuilder {
+    params: RetrievePersonParams,
+}
+
+impl RetrievePersonParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    pub fn person_id(mut self, person_id: impl Into<String>) -> Self {
+        self.params.person_id = person_id.into();
+        self
+    }
+
+    pub fn build(self) -> Result<RetrievePersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RetrievePersonResponse {
     pub status: i32,
@@ -302,7 +765,7 @@ pub struct RetrievePersonResponse {
     pub billed: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct BulkRetrievePersonParams {
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub base_params: Option<BaseParams>,
@@ -314,6 +777,10 @@ pub struct BulkRetrievePersonParams {
 }
 
 impl BulkRetrievePersonParams {
+    pub fn builder() -> BulkRetrievePersonParamsBuilder {
+        BulkRetrievePersonParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.requests.is_empty() {
             return Err(PDLError::ValidationError);
@@ -328,6 +795,44 @@ impl BulkRetrievePersonParams {
     }
 }
 
+/// Builder for `BulkRetrievePersonParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct BulkRetrievePersonParamsBuilder {
+    params: BulkRetrievePersonParams,
+}
+
+impl BulkRetrievePersonParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set_opt additional_params {
+        titlecase: bool,
+        data_include: String,
+    });
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.params.requests.push(BulkRetrieveSinglePersonParams {
+            id: id.into(),
+            metadata: None,
+        });
+        self
+    }
+
+    pub fn id_with_metadata(mut self, id: impl Into<String>, metadata: PersonMetadata) -> Self {
+        self.params.requests.push(BulkRetrieveSinglePersonParams {
+            id: id.into(),
+            metadata: Some(metadata),
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<BulkRetrievePersonParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BulkRetrieveSinglePersonParams {
     pub id: String, // The ID of a person
@@ -17,7 +17,7 @@ pub struct SchoolParams {
     pub profile: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct CleanSchoolParams {
     #[serde(flatten)]
     pub base_params: Option<BaseParams>,
@@ -30,6 +30,10 @@ pub struct CleanSchoolParams {
 }
 
 impl CleanSchoolParams {
+    pub fn builder() -> CleanSchoolParamsBuilder {
+        CleanSchoolParamsBuilder::default()
+    }
+
     pub fn validate(&self) -> Result<(), PDLError> {
         if self.school_params.name.is_none()
             && self.school_params.website.is_none()
@@ -41,6 +45,29 @@ impl CleanSchoolParams {
     }
 }
 
+/// Builder for `CleanSchoolParams`; `build()` runs `validate()`.
+#[derive(Debug, Default)]
+pub struct CleanSchoolParamsBuilder {
+    params: CleanSchoolParams,
+}
+
+impl CleanSchoolParamsBuilder {
+    setters!(set_opt base_params {
+        pretty: bool,
+    });
+
+    setters!(set school_params {
+        name: String,
+        website: String,
+        profile: String,
+    });
+
+    pub fn build(self) -> Result<CleanSchoolParams, PDLError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Location {
     pub name: Option<String>,
@@ -0,0 +1,359 @@
+use crate::PDLError;
+
+/// A one-line postal address split into the location fields PDL accepts.
+/// Regions and countries are PDL's lowercase full names ("california",
+/// "united states"); postal codes are uppercased.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub street_address: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub country: Option<String>,
+    pub postal_code: Option<String>,
+}
+
+const US: &str = "united states";
+const CANADA: &str = "canada";
+const UK: &str = "united kingdom";
+
+static COUNTRIES: &[(&str, &str)] = &[
+    ("us", US),
+    ("usa", US),
+    ("u.s.", US),
+    ("u.s.a.", US),
+    ("united states", US),
+    ("united states of america", US),
+    ("canada", CANADA),
+    ("uk", UK),
+    ("u.k.", UK),
+    ("gb", UK),
+    ("great britain", UK),
+    ("united kingdom", UK),
+    ("england", UK),
+    ("scotland", UK),
+    ("wales", UK),
+    ("northern ireland", UK),
+];
+
+/// US states and Canadian provinces: code, name, country.
+static REGIONS: &[(&str, &str, &str)] = &[
+    ("al", "alabama", US),
+    ("ak", "alaska", US),
+    ("az", "arizona", US),
+    ("ar", "arkansas", US),
+    ("ca", "california", US),
+    ("co", "colorado", US),
+    ("ct", "connecticut", US),
+    ("de", "delaware", US),
+    ("dc", "district of columbia", US),
+    ("fl", "florida", US),
+    ("ga", "georgia", US),
+    ("hi", "hawaii", US),
+    ("id", "idaho", US),
+    ("il", "illinois", US),
+    ("in", "indiana", US),
+    ("ia", "iowa", US),
+    ("ks", "kansas", US),
+    ("ky", "kentucky", US),
+    ("la", "louisiana", US),
+    ("me", "maine", US),
+    ("md", "maryland", US),
+    ("ma", "massachusetts", US),
+    ("mi", "michigan", US),
+    ("mn", "minnesota", US),
+    ("ms", "mississippi", US),
+    ("mo", "missouri", US),
+    ("mt", "montana", US),
+    ("ne", "nebraska", US),
+    ("nv", "nevada", US),
+    ("nh", "new hampshire", US),
+    ("nj", "new jersey", US),
+    ("nm", "new mexico", US),
+    ("ny", "new york", US),
+    ("nc", "north carolina", US),
+    ("nd", "north dakota", US),
+    ("oh", "ohio", US),
+    ("ok", "oklahoma", US),
+    ("or", "oregon", US),
+    ("pa", "pennsylvania", US),
+    ("pr", "puerto rico", US),
+    ("ri", "rhode island", US),
+    ("sc", "south carolina", US),
+    ("sd", "south dakota", US),
+    ("tn", "tennessee", US),
+    ("tx", "texas", US),
+    ("ut", "utah", US),
+    ("vt", "vermont", US),
+    ("va", "virginia", US),
+    ("wa", "washington", US),
+    ("wv", "west virginia", US),
+    ("wi", "wisconsin", US),
+    ("wy", "wyoming", US),
+    ("ab", "alberta", CANADA),
+    ("bc", "british columbia", CANADA),
+    ("mb", "manitoba", CANADA),
+    ("nb", "new brunswick", CANADA),
+    ("nl", "newfoundland and labrador", CANADA),
+    ("ns", "nova scotia", CANADA),
+    ("nt", "northwest territories", CANADA),
+    ("nu", "nunavut", CANADA),
+    ("on", "ontario", CANADA),
+    ("pe", "prince edward island", CANADA),
+    ("qc", "quebec", CANADA),
+    ("sk", "saskatchewan", CANADA),
+    ("yt", "yukon", CANADA),
+];
+
+fn lower(word: &str) -> String {
+    word.trim_matches('.').to_lowercase()
+}
+
+fn is_us_zip(code: &str) -> bool {
+    let (zip, plus4) = code.split_once('-').unwrap_or((code, "0000"));
+    zip.len() == 5
+        && plus4.len() == 4
+        && zip.chars().chain(plus4.chars()).all(|c| c.is_ascii_digit())
+}
+
+/// `A1A1A1`, already without the space.
+fn is_canadian_postal_code(code: &str) -> bool {
+    code.len() == 6
+        && code.chars().enumerate().all(|(i, c)| {
+            if i % 2 == 0 {
+                c.is_ascii_alphabetic()
+            } else {
+                c.is_ascii_digit()
+            }
+        })
+}
+
+/// A UK postcode without the space: an outward code (`A9`, `A99`, `AA9`,
+/// `AA99`, `A9A` or `AA9A`) followed by a `9AA` inward code.
+fn is_uk_postcode(code: &str) -> bool {
+    let Some(split) = code.len().checked_sub(3) else {
+        return false;
+    };
+    let (outward, inward) = code.split_at(split);
+    let inward: Vec<char> = inward.chars().collect();
+    let inward_ok = inward[0].is_ascii_digit()
+        && inward[1].is_ascii_alphabetic()
+        && inward[2].is_ascii_alphabetic();
+
+    let letters = outward
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let rest = &outward[letters..];
+    let outward_ok = (1..=2).contains(&letters)
+        && rest.starts_with(|c: char| c.is_ascii_digit())
+        && rest.len() <= 2
+        && rest.chars().all(|c| c.is_ascii_alphanumeric());
+
+    inward_ok && outward_ok
+}
+
+/// Finds a postal code at the end of `words`, returning it and how many
+/// words it took. Only formats of `country` are tried when it's known.
+fn postal_code(words: &[&str], country: Option<&str>) -> Option<(String, &'static str, usize)> {
+    let last = words.last()?.to_uppercase();
+    let last_two =
+        (words.len() >= 2).then(|| format!("{}{}", words[words.len() - 2], last).to_uppercase());
+    let allowed = |candidate: &str| country.is_none_or(|country| country == candidate);
+
+    if allowed(US) && is_us_zip(&last) {
+        return Some((last, US, 1));
+    }
+    for (code, taken) in [(last_two.clone(), 2), (Some(last.clone()), 1)] {
+        let Some(code) = code.filter(|code| code.is_ascii()) else {
+            continue;
+        };
+        let (outward, inward) = code.split_at(code.len().saturating_sub(3));
+        if allowed(CANADA) && is_canadian_postal_code(&code) {
+            return Some((format!("{} {}", outward, inward), CANADA, taken));
+        }
+        if allowed(UK) && is_uk_postcode(&code) {
+            return Some((format!("{} {}", outward, inward), UK, taken));
+        }
+    }
+    None
+}
+
+/// Finds a US state or Canadian province, by code or name, at the end of
+/// `words`, returning its name, country and how many words it took.
+fn region(words: &[&str], country: Option<&str>) -> Option<(&'static str, &'static str, usize)> {
+    (1..=words.len().min(4)).rev().find_map(|taken| {
+        let candidate = words[words.len() - taken..]
+            .iter()
+            .map(|word| lower(word))
+            .collect::<Vec<_>>()
+            .join(" ");
+        REGIONS
+            .iter()
+            .find(|(code, name, region_country)| {
+                (candidate == *code || candidate == *name)
+                    && country.is_none_or(|country| country == *region_country)
+            })
+            .map(|(_, name, region_country)| (*name, *region_country, taken))
+    })
+}
+
+/// Splits a one-line address such as "123 Main St, Springfield, IL 62701"
+/// or "10 Downing Street, London SW1A 2AA, UK" into street address,
+/// locality, region, country and postal code. US, Canadian and UK formats
+/// are recognized; the country is inferred from the postal code or region
+/// when missing. Fails with `PDLError::ValidationError` for empty input.
+pub fn parse_address(address: &str) -> Result<ParsedAddress, PDLError> {
+    let mut parts: Vec<&str> = address
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return Err(PDLError::ValidationError);
+    }
+
+    let mut parsed = ParsedAddress::default();
+    let mut country = parts.last().and_then(|part| {
+        COUNTRIES
+            .iter()
+            .find(|(name, _)| *name == part.to_lowercase())
+            .map(|(_, country)| *country)
+    });
+    if country.is_some() {
+        parts.pop();
+    }
+
+    // The last part holds the postal code and region ("IL 62701"), possibly
+    // after the locality when there's no comma ("Springfield IL 62701").
+    let mut words: Vec<&str> = parts
+        .last()
+        .map(|part| part.split_whitespace().collect())
+        .unwrap_or_default();
+    let tail_was_last_part = !words.is_empty();
+
+    if let Some((code, code_country, taken)) = postal_code(&words, country) {
+        parsed.postal_code = Some(code);
+        country = country.or(Some(code_country));
+        words.truncate(words.len() - taken);
+    }
+    if country != Some(UK) {
+        if let Some((name, region_country, taken)) = region(&words, country) {
+            parsed.region = Some(name.to_string());
+            country = country.or(Some(region_country));
+            words.truncate(words.len() - taken);
+        }
+    }
+
+    if tail_was_last_part {
+        parts.pop();
+    }
+    if !words.is_empty() {
+        parsed.locality = Some(words.join(" "));
+    } else if let Some(locality) = parts.pop() {
+        parsed.locality = Some(locality.to_string());
+    }
+
+    parsed.street_address = (!parts.is_empty()).then(|| parts.join(", "));
+    parsed.country = country.map(str::to_string);
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_address, ParsedAddress};
+
+    #[test]
+    fn test_parse_address() {
+        let address = |street: Option<&str>,
+                       locality: &str,
+                       region: Option<&str>,
+                       country: &str,
+                       postal_code: Option<&str>| ParsedAddress {
+            street_address: street.map(str::to_string),
+            locality: Some(locality.to_string()),
+            region: region.map(str::to_string),
+            country: Some(country.to_string()),
+            postal_code: postal_code.map(str::to_string),
+        };
+        let cases = [
+            (
+                "123 Main St, Suite 4, Springfield, IL 62701",
+                address(
+                    Some("123 Main St, Suite 4"),
+                    "Springfield",
+                    Some("illinois"),
+                    "united states",
+                    Some("62701"),
+                ),
+            ),
+            (
+                "1600 Pennsylvania Ave NW, Washington, DC 20500-0003, USA",
+                address(
+                    Some("1600 Pennsylvania Ave NW"),
+                    "Washington",
+                    Some("district of columbia"),
+                    "united states",
+                    Some("20500-0003"),
+                ),
+            ),
+            (
+                "San Francisco, California",
+                address(
+                    None,
+                    "San Francisco",
+                    Some("california"),
+                    "united states",
+                    None,
+                ),
+            ),
+            (
+                "Salt Lake City UT 84101",
+                address(
+                    None,
+                    "Salt Lake City",
+                    Some("utah"),
+                    "united states",
+                    Some("84101"),
+                ),
+            ),
+            (
+                "301 Front St W, Toronto, ON M5V 2T6, Canada",
+                address(
+                    Some("301 Front St W"),
+                    "Toronto",
+                    Some("ontario"),
+                    "canada",
+                    Some("M5V 2T6"),
+                ),
+            ),
+            (
+                "10 Downing Street, London SW1A 2AA, UK",
+                address(
+                    Some("10 Downing Street"),
+                    "London",
+                    None,
+                    "united kingdom",
+                    Some("SW1A 2AA"),
+                ),
+            ),
+            (
+                "221B Baker Street\nLondon\nnw16xe",
+                address(
+                    Some("221B Baker Street"),
+                    "London",
+                    None,
+                    "united kingdom",
+                    Some("NW1 6XE"),
+                ),
+            ),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(parse_address(raw).expect(raw), expected, "{}", raw);
+        }
+
+        let parsed = parse_address("Zürich").expect("ERROR");
+        assert_eq!(parsed.locality.as_deref(), Some("Zürich"));
+        assert!(parsed.country.is_none());
+        assert!(parse_address(" , ").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::{util::hex, PDLError};
+
+/// How much an email is rewritten before hashing. PDL hashes the trimmed,
+/// lowercased address, so anything beyond `Basic` trades exact matches for
+/// catching aliases of the same mailbox.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmailNormalization {
+    /// Trim whitespace and lowercase.
+    #[default]
+    Basic,
+    /// `Basic`, and for Gmail addresses also drop dots and `+tags` from the
+    /// local part and map `googlemail.com` to `gmail.com`.
+    Gmail,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmailHashAlgorithm {
+    #[default]
+    Sha256,
+    Md5,
+}
+
+/// How emails are turned into `email_hash` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmailHashing {
+    pub normalization: EmailNormalization,
+    pub algorithm: EmailHashAlgorithm,
+}
+
+impl EmailHashing {
+    /// Normalizes and hashes `email`, see `hash_email`.
+    pub fn hash(&self, email: &str) -> Result<String, PDLError> {
+        hash_email(email, self.normalization, self.algorithm)
+    }
+}
+
+/// Normalizes an email address. Fails with `PDLError::ValidationError` if
+/// it isn't of the form `local@domain`.
+pub fn normalize_email(email: &str, normalization: EmailNormalization) -> Result<String, PDLError> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@').ok_or(PDLError::ValidationError)?;
+    if local.is_empty() || domain.is_empty() || domain.contains('@') {
+        return Err(PDLError::ValidationError);
+    }
+
+    match normalization {
+        EmailNormalization::Gmail if domain == "gmail.com" || domain == "googlemail.com" => {
+            let local = local.split('+').next().unwrap_or_default().replace('.', "");
+            if local.is_empty() {
+                return Err(PDLError::ValidationError);
+            }
+            Ok(format!("{}@gmail.com", local))
+        }
+        _ => Ok(email),
+    }
+}
+
+/// Normalizes `email` and returns its lowercase hex digest.
+pub fn hash_email(
+    email: &str,
+    normalization: EmailNormalization,
+    algorithm: EmailHashAlgorithm,
+) -> Result<String, PDLError> {
+    let email = normalize_email(email, normalization)?;
+    let digest = match algorithm {
+        EmailHashAlgorithm::Sha256 => Sha256::digest(email.as_bytes()).to_vec(),
+        EmailHashAlgorithm::Md5 => Md5::digest(email.as_bytes()).to_vec(),
+    };
+    Ok(hex(&digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_email, normalize_email, EmailHashAlgorithm, EmailNormalization};
+
+    #[test]
+    fn test_hash_email() {
+        assert_eq!(
+            hash_email(
+                "  Test@Example.com ",
+                EmailNormalization::Basic,
+                EmailHashAlgorithm::Sha256
+            )
+            .expect("ERROR"),
+            "973dfe463ec85785f5f95af5ba3906eedb2d931c24e69824a89ea65dba4e813b"
+        );
+        assert_eq!(
+            hash_email(
+                "test@example.com",
+                EmailNormalization::Basic,
+                EmailHashAlgorithm::Md5
+            )
+            .expect("ERROR"),
+            "55502f40dc8b7c769880b10874abc9d0"
+        );
+
+        assert_eq!(
+            normalize_email("John.Smith+news@GoogleMail.com", EmailNormalization::Gmail)
+                .expect("ERROR"),
+            "johnsmith@gmail.com"
+        );
+        assert_eq!(
+            normalize_email("john.smith+news@example.com", EmailNormalization::Gmail)
+                .expect("ERROR"),
+            "john.smith+news@example.com"
+        );
+        assert!(normalize_email("not an email", EmailNormalization::Basic).is_err());
+        assert!(normalize_email("a@b@c", EmailNormalization::Basic).is_err());
+    }
+}
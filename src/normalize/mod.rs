@@ -0,0 +1,13 @@
+pub mod address;
+pub mod email;
+pub mod name;
+pub mod phone;
+pub mod profile;
+
+pub use address::{parse_address, ParsedAddress};
+pub use email::{
+    hash_email, normalize_email, EmailHashAlgorithm, EmailHashing, EmailNormalization,
+};
+pub use name::{parse_name, ParsedName};
+pub use phone::normalize_phone;
+pub use profile::{canonicalize_profile, ProfileKind, SocialNetwork, SocialProfile};
@@ -0,0 +1,178 @@
+use crate::PDLError;
+
+/// A personal name split into its parts by `parse_name`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedName {
+    /// Honorifics such as "Dr." or "Mrs.", as written.
+    pub prefix: Option<String>,
+    pub first: Option<String>,
+    /// Every given name after the first, with initials' periods removed.
+    pub middle: Option<String>,
+    /// The family name, including particles ("van der Berg") and hyphens.
+    pub last: Option<String>,
+    /// Generational and professional suffixes such as "Jr." or "PhD", as
+    /// written.
+    pub suffix: Option<String>,
+}
+
+static HONORIFICS: &[&str] = &[
+    "capt", "col", "dame", "dr", "fr", "gen", "hon", "lady", "lord", "lt", "maj", "miss", "mr",
+    "mrs", "ms", "mx", "prof", "rev", "sgt", "sir",
+];
+
+static SUFFIXES: &[&str] = &[
+    "ii", "iii", "iv", "v", "jr", "sr", "cpa", "dds", "esq", "jd", "md", "mba", "phd", "pe", "rn",
+];
+
+/// Lowercase words that join the following word into the last name.
+static PARTICLES: &[&str] = &[
+    "abu", "al", "bin", "binti", "da", "das", "de", "del", "della", "der", "di", "dos", "du", "el",
+    "ibn", "la", "le", "st", "ten", "ter", "van", "von",
+];
+
+fn bare(word: &str) -> String {
+    word.trim_matches(|c: char| c == '.' || c == ',')
+        .to_lowercase()
+}
+
+fn is_honorific(word: &str) -> bool {
+    HONORIFICS.contains(&bare(word).as_str())
+}
+
+fn is_suffix(word: &str) -> bool {
+    SUFFIXES.contains(&bare(word).replace('.', "").as_str())
+}
+
+fn is_particle(word: &str) -> bool {
+    PARTICLES.contains(&bare(word).as_str())
+}
+
+/// "Q." and "Q" become "Q"; other words are kept as written.
+fn given(word: &str) -> String {
+    match word.strip_suffix('.') {
+        Some(initial) if initial.chars().count() == 1 => initial.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+fn join(words: &[&str]) -> Option<String> {
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Splits a full name into honorific, first, middle, last and suffix.
+/// Handles "Last, First Middle" order, particles ("Ludwig van Beethoven",
+/// "Omar al-Farouq") and trailing suffixes ("Jane Doe, PhD"). A single word
+/// is taken as the first name. Fails with `PDLError::ValidationError` if
+/// there's no name left once honorifics and suffixes are removed.
+pub fn parse_name(name: &str) -> Result<ParsedName, PDLError> {
+    let mut parts: Vec<&str> = name
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    // Trailing comma-separated parts made only of suffixes ("Doe, Jane, Jr.").
+    let mut suffixes: Vec<&str> = Vec::new();
+    while parts.len() > 1 && parts[parts.len() - 1].split_whitespace().all(is_suffix) {
+        let part = parts.pop().unwrap_or_default();
+        suffixes.splice(0..0, part.split_whitespace());
+    }
+
+    // "Last, First Middle": everything before the first comma is the last name.
+    let (explicit_last, rest) = match parts.as_slice() {
+        [] => (None, String::new()),
+        [whole] => (None, whole.to_string()),
+        [last, rest @ ..] => (Some(*last), rest.join(" ")),
+    };
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+
+    let honorifics = words.iter().take_while(|word| is_honorific(word)).count();
+    let prefix = join(&words[..honorifics]);
+    words.drain(..honorifics);
+
+    // Keep at least a first and last name when stripping suffixes, so a
+    // surname like "V" isn't mistaken for one.
+    let keep = if explicit_last.is_some() { 1 } else { 2 };
+    let mut trailing: Vec<&str> = Vec::new();
+    while words.len() > keep && words.last().is_some_and(|word| is_suffix(word)) {
+        trailing.insert(0, words.pop().unwrap_or_default());
+    }
+    trailing.extend(suffixes);
+    let suffix = join(&trailing).map(|suffix| suffix.trim_end_matches(',').to_string());
+
+    let (given_names, last) = match explicit_last {
+        Some(last) => (&words[..], Some(last.to_string())),
+        None if words.len() < 2 => (&words[..], None),
+        None => {
+            let mut start = words.len() - 1;
+            while start > 1 && is_particle(words[start - 1]) {
+                start -= 1;
+            }
+            (&words[..start], join(&words[start..]))
+        }
+    };
+
+    let given_names: Vec<String> = given_names
+        .iter()
+        .map(|word| given(word.trim_end_matches(',')))
+        .collect();
+    let Some((first, middle)) = given_names.split_first() else {
+        return Err(PDLError::ValidationError);
+    };
+
+    Ok(ParsedName {
+        prefix,
+        first: Some(first.clone()),
+        middle: (!middle.is_empty()).then(|| middle.join(" ")),
+        last,
+        suffix,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_name, ParsedName};
+
+    #[test]
+    fn test_parse_name() {
+        let parsed = parse_name("Dr. Jane Q. Doe-Smith Jr.").expect("ERROR");
+        assert_eq!(
+            parsed,
+            ParsedName {
+                prefix: Some("Dr.".to_string()),
+                first: Some("Jane".to_string()),
+                middle: Some("Q".to_string()),
+                last: Some("Doe-Smith".to_string()),
+                suffix: Some("Jr.".to_string()),
+            }
+        );
+
+        let cases = [
+            ("Doe, Jane Quinn", ("Jane", Some("Quinn"), Some("Doe"))),
+            ("Doe, Jane, PhD", ("Jane", None, Some("Doe"))),
+            ("Jane Doe, MD", ("Jane", None, Some("Doe"))),
+            (
+                "Ludwig van Beethoven",
+                ("Ludwig", None, Some("van Beethoven")),
+            ),
+            (
+                "Anna Maria van der Berg",
+                ("Anna", Some("Maria"), Some("van der Berg")),
+            ),
+            ("Omar al-Farouq", ("Omar", None, Some("al-Farouq"))),
+            ("Maria de la Cruz", ("Maria", None, Some("de la Cruz"))),
+            ("Malcolm X", ("Malcolm", None, Some("X"))),
+            ("Mr Sean   Thorne III", ("Sean", None, Some("Thorne"))),
+            ("Cher", ("Cher", None, None)),
+        ];
+        for (name, (first, middle, last)) in cases {
+            let parsed = parse_name(name).expect(name);
+            assert_eq!(parsed.first.as_deref(), Some(first), "{}", name);
+            assert_eq!(parsed.middle.as_deref(), middle, "{}", name);
+            assert_eq!(parsed.last.as_deref(), last, "{}", name);
+        }
+
+        assert!(parse_name("").is_err());
+        assert!(parse_name("Dr.").is_err());
+    }
+}
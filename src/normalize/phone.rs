@@ -0,0 +1,175 @@
+use crate::PDLError;
+
+/// Dialing rules for a region: ISO 3166-1 alpha-2 code, country calling
+/// code, national trunk prefix (dropped in E.164) and the range of national
+/// significant number lengths.
+struct Region {
+    code: &'static str,
+    calling_code: &'static str,
+    trunk_prefix: &'static str,
+    lengths: (usize, usize),
+}
+
+const fn region(
+    code: &'static str,
+    calling_code: &'static str,
+    trunk_prefix: &'static str,
+    lengths: (usize, usize),
+) -> Region {
+    Region {
+        code,
+        calling_code,
+        trunk_prefix,
+        lengths,
+    }
+}
+
+/// Regions `normalize_phone` knows how to read national numbers for. Numbers
+/// already in international form may use any calling code.
+static REGIONS: &[Region] = &[
+    region("US", "1", "1", (10, 10)),
+    region("CA", "1", "1", (10, 10)),
+    region("GB", "44", "0", (9, 10)),
+    region("IE", "353", "0", (7, 9)),
+    region("AU", "61", "0", (9, 9)),
+    region("NZ", "64", "0", (8, 10)),
+    region("DE", "49", "0", (6, 11)),
+    region("FR", "33", "0", (9, 9)),
+    region("NL", "31", "0", (9, 9)),
+    region("BE", "32", "0", (8, 9)),
+    region("CH", "41", "0", (9, 9)),
+    region("AT", "43", "0", (7, 13)),
+    region("SE", "46", "0", (7, 9)),
+    region("NO", "47", "", (8, 8)),
+    region("DK", "45", "", (8, 8)),
+    region("FI", "358", "0", (6, 10)),
+    region("ES", "34", "", (9, 9)),
+    region("PT", "351", "", (9, 9)),
+    // Italian numbers keep their leading zero.
+    region("IT", "39", "", (6, 11)),
+    region("PL", "48", "", (9, 9)),
+    region("IL", "972", "0", (8, 9)),
+    region("AE", "971", "0", (8, 9)),
+    region("IN", "91", "0", (10, 10)),
+    region("SG", "65", "", (8, 8)),
+    region("HK", "852", "", (8, 8)),
+    region("JP", "81", "0", (9, 10)),
+    region("KR", "82", "0", (8, 10)),
+    region("CN", "86", "0", (10, 11)),
+    region("PH", "63", "0", (8, 10)),
+    region("BR", "55", "0", (10, 11)),
+    region("MX", "52", "", (10, 10)),
+    region("AR", "54", "0", (10, 10)),
+    region("CO", "57", "", (10, 10)),
+    region("ZA", "27", "0", (9, 9)),
+    region("NG", "234", "0", (8, 10)),
+];
+
+/// Shortest and longest digit counts E.164 allows after the `+`.
+const E164_LENGTHS: (usize, usize) = (8, 15);
+
+/// Normalizes a phone number to E.164 (`+14155550123`). Extensions
+/// ("x12", "ext. 12", "#12") are dropped. Numbers without a `+` or an
+/// international dialing prefix are read as national numbers of
+/// `default_region`, an ISO 3166-1 alpha-2 code such as `"US"`. Fails with
+/// `PDLError::ValidationError` for letters, unknown regions and numbers of
+/// the wrong length.
+pub fn normalize_phone(phone: &str, default_region: &str) -> Result<String, PDLError> {
+    let phone = strip_extension(phone).trim();
+    if phone.chars().any(char::is_alphabetic) {
+        return Err(PDLError::ValidationError);
+    }
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+
+    let home = REGIONS
+        .iter()
+        .find(|region| region.code.eq_ignore_ascii_case(default_region.trim()))
+        .ok_or(PDLError::ValidationError)?;
+    let exit_code = if home.calling_code == "1" {
+        "011"
+    } else {
+        "00"
+    };
+
+    if phone.starts_with('+') {
+        international(&digits)
+    } else if let Some(digits) = digits.strip_prefix(exit_code) {
+        international(digits)
+    } else {
+        national(home, &digits)
+    }
+}
+
+fn strip_extension(phone: &str) -> &str {
+    let lower = phone.to_ascii_lowercase();
+    let end = ["ext", "x", "#", ";"]
+        .iter()
+        .filter_map(|marker| lower.find(marker))
+        .min()
+        .unwrap_or(phone.len());
+    &phone[..end]
+}
+
+/// `digits` starts with the calling code.
+fn international(digits: &str) -> Result<String, PDLError> {
+    let known = REGIONS
+        .iter()
+        .find(|region| digits.starts_with(region.calling_code));
+
+    match known {
+        Some(region) => national(region, &digits[region.calling_code.len()..]),
+        None if (E164_LENGTHS.0..=E164_LENGTHS.1).contains(&digits.len()) => {
+            Ok(format!("+{}", digits))
+        }
+        None => Err(PDLError::ValidationError),
+    }
+}
+
+/// `digits` is a national number of `region`, possibly with its trunk
+/// prefix (as in "+44 (0)20 ...").
+fn national(region: &Region, digits: &str) -> Result<String, PDLError> {
+    let (min, max) = region.lengths;
+    let number = match digits.strip_prefix(region.trunk_prefix) {
+        Some(rest) if !region.trunk_prefix.is_empty() && rest.len() >= min => rest,
+        _ => digits,
+    };
+
+    if !(min..=max).contains(&number.len()) {
+        return Err(PDLError::ValidationError);
+    }
+    Ok(format!("+{}{}", region.calling_code, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_phone;
+
+    #[test]
+    fn test_normalize_phone() {
+        let cases = [
+            ("(555) 123-4567 x12", "US", "+15551234567"),
+            ("1-555-123-4567 ext. 9", "US", "+15551234567"),
+            ("+1 (555) 123-4567", "GB", "+15551234567"),
+            ("+44 20 7946 0958", "US", "+442079460958"),
+            ("+44 (0)20 7946 0958", "US", "+442079460958"),
+            ("020 7946 0958", "gb", "+442079460958"),
+            ("0044 20 7946 0958", "DE", "+442079460958"),
+            ("011 44 20 7946 0958", "US", "+442079460958"),
+            ("06 12 34 56 78", "FR", "+33612345678"),
+            ("06 1234 5678", "IT", "+390612345678"),
+            ("+7 495 123-45-67", "US", "+74951234567"),
+        ];
+        for (phone, region, expected) in cases {
+            assert_eq!(
+                normalize_phone(phone, region).expect(phone),
+                expected,
+                "{}",
+                phone
+            );
+        }
+
+        assert!(normalize_phone("555-1234", "US").is_err());
+        assert!(normalize_phone("1-800-FLOWERS", "US").is_err());
+        assert!(normalize_phone("555 123 4567", "XX").is_err());
+    }
+}
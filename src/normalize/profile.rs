@@ -0,0 +1,355 @@
+use crate::PDLError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocialNetwork {
+    LinkedIn,
+    Twitter,
+    Facebook,
+    GitHub,
+    Crunchbase,
+}
+
+/// Whether a profile belongs to a person, a company or a school, when the
+/// URL says.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileKind {
+    Person,
+    Company,
+    /// A LinkedIn school page.
+    School,
+}
+
+/// A social profile parsed from a URL by `canonicalize_profile`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SocialProfile {
+    pub network: SocialNetwork,
+    /// `None` for networks where people and companies share one namespace
+    /// (Twitter, Facebook, GitHub users).
+    pub kind: Option<ProfileKind>,
+    /// Lowercased username or slug.
+    pub username: Option<String>,
+    /// Numeric id, for URLs that only carry one (LinkedIn `profile/view?id=`
+    /// and Facebook `profile.php?id=` links).
+    pub numeric_id: Option<String>,
+}
+
+impl SocialProfile {
+    /// The URL in the form PDL expects, e.g. `linkedin.com/in/seanthorne`.
+    /// `None` for LinkedIn profiles only known by numeric id, which PDL takes
+    /// as `lid` instead.
+    pub fn url(&self) -> Option<String> {
+        let username = self.username.as_deref();
+        match (self.network, self.kind, username) {
+            (SocialNetwork::LinkedIn, Some(ProfileKind::Company), Some(name)) => {
+                Some(format!("linkedin.com/company/{}", name))
+            }
+            (SocialNetwork::LinkedIn, Some(ProfileKind::School), Some(name)) => {
+                Some(format!("linkedin.com/school/{}", name))
+            }
+            (SocialNetwork::LinkedIn, _, Some(name)) => Some(format!("linkedin.com/in/{}", name)),
+            (SocialNetwork::LinkedIn, _, None) => None,
+            (SocialNetwork::Twitter, _, name) => name.map(|name| format!("twitter.com/{}", name)),
+            (SocialNetwork::Facebook, _, name) => name
+                .or(self.numeric_id.as_deref())
+                .map(|name| format!("facebook.com/{}", name)),
+            (SocialNetwork::GitHub, _, name) => name.map(|name| format!("github.com/{}", name)),
+            (SocialNetwork::Crunchbase, kind, name) => {
+                let section = match kind {
+                    Some(ProfileKind::Company) => "organization",
+                    _ => "person",
+                };
+                name.map(|name| format!("crunchbase.com/{}/{}", section, name))
+            }
+        }
+    }
+}
+
+/// Paths on Twitter, Facebook and GitHub that look like usernames but
+/// aren't profiles.
+static RESERVED_PATHS: &[&str] = &[
+    "about",
+    "events",
+    "explore",
+    "features",
+    "groups",
+    "hashtag",
+    "home",
+    "i",
+    "intent",
+    "login",
+    "marketplace",
+    "messages",
+    "notifications",
+    "pricing",
+    "search",
+    "settings",
+    "share",
+    "sharer",
+    "sharer.php",
+    "signup",
+    "topics",
+    "watch",
+];
+
+/// Parses a LinkedIn, Twitter/X, Facebook, GitHub or Crunchbase profile URL,
+/// ignoring scheme, `www.`/mobile/country subdomains, query strings,
+/// fragments and trailing slashes. Fails with `PDLError::ValidationError`
+/// for other sites and for URLs that aren't a profile (feeds, posts, search
+/// pages, ...).
+pub fn canonicalize_profile(url: &str) -> Result<SocialProfile, PDLError> {
+    let url = url.trim();
+    let lower = url.to_ascii_lowercase();
+    let rest = ["https://", "http://", "//"]
+        .iter()
+        .find_map(|scheme| lower.strip_prefix(scheme))
+        .unwrap_or(&lower);
+
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, query.split('#').next().unwrap_or_default()),
+        None => (rest.split('#').next().unwrap_or_default(), ""),
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = host.split(':').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let profile = match site(host) {
+        Some(SocialNetwork::LinkedIn) => linkedin(&segments, query),
+        Some(SocialNetwork::Twitter) => single_username(SocialNetwork::Twitter, &segments, |c| {
+            c.is_ascii_alphanumeric() || c == '_'
+        }),
+        Some(SocialNetwork::Facebook) => facebook(&segments, query),
+        Some(SocialNetwork::GitHub) => github(&segments),
+        Some(SocialNetwork::Crunchbase) => crunchbase(&segments),
+        None => None,
+    };
+    profile.ok_or(PDLError::ValidationError)
+}
+
+/// The network served from `host`, allowing `www.`, mobile and LinkedIn's
+/// country subdomains.
+fn site(host: &str) -> Option<SocialNetwork> {
+    let parts: Vec<&str> = host.split('.').collect();
+    let domain = parts.len().checked_sub(2).map(|i| parts[i..].join("."))?;
+    let subdomain = &parts[..parts.len() - 2];
+
+    let network = match domain.as_str() {
+        "linkedin.com" => SocialNetwork::LinkedIn,
+        "twitter.com" | "x.com" => SocialNetwork::Twitter,
+        "facebook.com" | "fb.com" => SocialNetwork::Facebook,
+        "github.com" => SocialNetwork::GitHub,
+        "crunchbase.com" => SocialNetwork::Crunchbase,
+        _ => return None,
+    };
+    let subdomain_ok = match subdomain {
+        [] => true,
+        ["www" | "m" | "mobile"] => true,
+        ["mbasic" | "touch" | "web"] => network == SocialNetwork::Facebook,
+        [country] => network == SocialNetwork::LinkedIn && country.len() == 2,
+        _ => false,
+    };
+    subdomain_ok.then_some(network)
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn numeric(value: &str) -> Option<String> {
+    (!value.is_empty() && value.chars().all(|c| c.is_ascii_digit())).then(|| value.to_string())
+}
+
+fn slug(value: &str, allowed: impl Fn(char) -> bool) -> Option<String> {
+    (!value.is_empty() && value.chars().all(allowed)).then(|| value.to_string())
+}
+
+fn linkedin(segments: &[&str], query: &str) -> Option<SocialProfile> {
+    let (kind, username, numeric_id) = match segments {
+        ["in", name, ..] => (ProfileKind::Person, Some(name), None),
+        ["company", name, ..] => (ProfileKind::Company, Some(name), None),
+        ["school", name, ..] => (ProfileKind::School, Some(name), None),
+        ["profile", "view", ..] => (
+            ProfileKind::Person,
+            None,
+            Some(numeric(query_param(query, "id")?)?),
+        ),
+        _ => return None,
+    };
+    let username = match username {
+        Some(name) => Some(slug(name, |c| {
+            c.is_alphanumeric() || matches!(c, '-' | '_' | '%')
+        })?),
+        None => None,
+    };
+    Some(SocialProfile {
+        network: SocialNetwork::LinkedIn,
+        kind: Some(kind),
+        username,
+        numeric_id,
+    })
+}
+
+fn single_username(
+    network: SocialNetwork,
+    segments: &[&str],
+    allowed: impl Fn(char) -> bool,
+) -> Option<SocialProfile> {
+    let [name] = segments else {
+        return None;
+    };
+    if RESERVED_PATHS.contains(name) {
+        return None;
+    }
+    Some(SocialProfile {
+        network,
+        kind: None,
+        username: Some(slug(name, allowed)?),
+        numeric_id: None,
+    })
+}
+
+fn facebook(segments: &[&str], query: &str) -> Option<SocialProfile> {
+    let numeric_id = match segments {
+        ["profile.php"] => numeric(query_param(query, "id")?)?,
+        ["people", _, id] => numeric(id)?,
+        [id] if numeric(id).is_some() => id.to_string(),
+        _ => {
+            return single_username(SocialNetwork::Facebook, segments, |c| {
+                c.is_ascii_alphanumeric() || c == '.'
+            })
+        }
+    };
+    Some(SocialProfile {
+        network: SocialNetwork::Facebook,
+        kind: None,
+        username: None,
+        numeric_id: Some(numeric_id),
+    })
+}
+
+fn github(segments: &[&str]) -> Option<SocialProfile> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-';
+    match segments {
+        ["orgs", name, ..] => Some(SocialProfile {
+            network: SocialNetwork::GitHub,
+            kind: Some(ProfileKind::Company),
+            username: Some(slug(name, allowed)?),
+            numeric_id: None,
+        }),
+        _ => single_username(SocialNetwork::GitHub, segments, allowed),
+    }
+}
+
+fn crunchbase(segments: &[&str]) -> Option<SocialProfile> {
+    let (kind, name) = match segments {
+        ["person", name, ..] => (ProfileKind::Person, name),
+        ["organization", name, ..] => (ProfileKind::Company, name),
+        _ => return None,
+    };
+    Some(SocialProfile {
+        network: SocialNetwork::Crunchbase,
+        kind: Some(kind),
+        username: Some(slug(name, |c| c.is_ascii_alphanumeric() || c == '-')?),
+        numeric_id: None,
+    })
+}
+
+/// The canonical URL of a company profile. Person profiles and profiles
+/// without a URL fail with `PDLError::ValidationError`.
+pub(crate) fn canonical_company_profile(url: &str) -> Result<String, PDLError> {
+    let profile = canonicalize_profile(url)?;
+    if profile.kind == Some(ProfileKind::Person) {
+        return Err(PDLError::ValidationError);
+    }
+    profile.url().ok_or(PDLError::ValidationError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonicalize_profile, ProfileKind};
+
+    #[test]
+    fn test_canonicalize_profile() {
+        let cases = [
+            (
+                "https://www.linkedin.com/in/SeanThorne/?trk=public_profile",
+                "linkedin.com/in/seanthorne",
+            ),
+            (
+                "http://m.linkedin.com/in/seanthorne/en",
+                "linkedin.com/in/seanthorne",
+            ),
+            (
+                "uk.linkedin.com/in/seanthorne#about",
+                "linkedin.com/in/seanthorne",
+            ),
+            (
+                "linkedin.com/company/peopledatalabs/about/",
+                "linkedin.com/company/peopledatalabs",
+            ),
+            (
+                "https://www.linkedin.com/school/stanford-university/",
+                "linkedin.com/school/stanford-university",
+            ),
+            (
+                "https://x.com/PeopleDataLabs?s=20",
+                "twitter.com/peopledatalabs",
+            ),
+            (
+                "mobile.twitter.com/peopledatalabs/",
+                "twitter.com/peopledatalabs",
+            ),
+            (
+                "https://m.facebook.com/sean.thorne",
+                "facebook.com/sean.thorne",
+            ),
+            (
+                "https://www.facebook.com/profile.php?id=100004&ref=bookmarks",
+                "facebook.com/100004",
+            ),
+            (
+                "https://github.com/peopledatalabs",
+                "github.com/peopledatalabs",
+            ),
+            (
+                "github.com/orgs/peopledatalabs/people",
+                "github.com/peopledatalabs",
+            ),
+            (
+                "https://www.crunchbase.com/organization/people-data-labs/company_financials",
+                "crunchbase.com/organization/people-data-labs",
+            ),
+        ];
+        for (url, expected) in cases {
+            let profile = canonicalize_profile(url).expect(url);
+            assert_eq!(profile.url().as_deref(), Some(expected), "{}", url);
+        }
+
+        let profile =
+            canonicalize_profile("https://www.linkedin.com/profile/view?id=145826&trk=nav")
+                .expect("ERROR");
+        assert_eq!(profile.numeric_id.as_deref(), Some("145826"));
+        assert_eq!(profile.kind, Some(ProfileKind::Person));
+        assert!(profile.url().is_none());
+
+        let profile =
+            canonicalize_profile("linkedin.com/school/stanford-university").expect("ERROR");
+        assert_eq!(profile.kind, Some(ProfileKind::School));
+
+        for url in [
+            "https://www.linkedin.com/feed/",
+            "https://www.linkedin.com/jobs/view/123",
+            "https://twitter.com/peopledatalabs/status/123",
+            "https://twitter.com/search?q=pdl",
+            "https://www.facebook.com/groups/rust",
+            "https://github.com/peopledatalabs/peopledatalabs-rust",
+            "https://www.example.com/in/seanthorne",
+            "https://notlinkedin.com/in/seanthorne",
+        ] {
+            assert!(canonicalize_profile(url).is_err(), "{}", url);
+        }
+    }
+}
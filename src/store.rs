@@ -0,0 +1,684 @@
+//! SQLite store of enriched records, behind the `store` feature.
+
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    api::{Company, Person},
+    models::{
+        company::{CompanyParams, CompanyResponse, EnrichCompanyParams},
+        jobposting::JobPosting as JobPostingRecord,
+        person::{EnrichPersonParams, Person as PersonRecord, PersonParams},
+    },
+    normalize::{canonicalize_profile, normalize_email, EmailNormalization},
+    util::unix_seconds,
+    PDLError,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS records (
+        kind TEXT NOT NULL,
+        id TEXT NOT NULL,
+        dataset_version TEXT,
+        fetched_at INTEGER NOT NULL,
+        body TEXT NOT NULL,
+        PRIMARY KEY (kind, id)
+    );
+    CREATE TABLE IF NOT EXISTS lookups (
+        kind TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        id TEXT NOT NULL,
+        PRIMARY KEY (kind, key, value, id)
+    );
+    CREATE INDEX IF NOT EXISTS lookups_by_id ON lookups (kind, id);
+";
+
+/// A value a stored record can be found by. Values are normalized the same
+/// way when records are stored and when they're looked up, so
+/// `Jane.Doe@Example.com` finds `jane.doe@example.com` and
+/// `https://www.linkedin.com/in/JaneDoe/` finds `linkedin.com/in/janedoe`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Email(String),
+    /// A social profile URL.
+    Profile(String),
+    /// A company website or domain.
+    Website(String),
+}
+
+impl Lookup {
+    /// The column name and normalized value, or `None` if the value can't
+    /// be normalized (e.g. an email without `@`).
+    fn key(&self) -> Option<(&'static str, String)> {
+        match self {
+            Lookup::Email(email) => normalize_email(email, EmailNormalization::Basic)
+                .ok()
+                .map(|email| ("email", email)),
+            Lookup::Profile(url) => {
+                let url = canonicalize_profile(url)
+                    .ok()
+                    .and_then(|profile| profile.url())
+                    .unwrap_or_else(|| bare_url(url).trim_end_matches('/').to_string());
+                (!url.is_empty()).then_some(("profile", url))
+            }
+            Lookup::Website(website) => {
+                let host = bare_url(website)
+                    .split(['/', '?', '#', ':'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                (!host.is_empty()).then_some(("website", host))
+            }
+        }
+    }
+}
+
+/// Lowercases `url` and strips its scheme and `www.`.
+fn bare_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let rest = ["https://", "http://", "//"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .unwrap_or(&url);
+    rest.strip_prefix("www.").unwrap_or(rest).to_string()
+}
+
+/// A record type the store can hold: `Person`, `CompanyResponse` or
+/// `JobPosting`.
+pub trait StorableRecord: Serialize + DeserializeOwned {
+    /// Keeps each record type apart in the store.
+    const KIND: &'static str;
+
+    fn record_id(&self) -> Option<&str>;
+
+    fn record_dataset_version(&self) -> Option<&str>;
+
+    /// Values the record can be found by besides its id.
+    fn lookups(&self) -> Vec<Lookup>;
+}
+
+fn emails<'a, I>(values: I) -> impl Iterator<Item = Lookup> + 'a
+where
+    I: IntoIterator<Item = &'a String>,
+    I::IntoIter: 'a,
+{
+    values.into_iter().map(|value| Lookup::Email(value.clone()))
+}
+
+fn profiles<'a, I>(values: I) -> impl Iterator<Item = Lookup> + 'a
+where
+    I: IntoIterator<Item = &'a String>,
+    I::IntoIter: 'a,
+{
+    values
+        .into_iter()
+        .map(|value| Lookup::Profile(value.clone()))
+}
+
+impl StorableRecord for PersonRecord {
+    const KIND: &'static str = "person";
+
+    fn record_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn record_dataset_version(&self) -> Option<&str> {
+        self.dataset_version.as_deref()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        let listed_emails = self
+            .emails
+            .iter()
+            .flatten()
+            .filter_map(|email| email.address.as_ref());
+        let listed_profiles = self
+            .profiles
+            .iter()
+            .flatten()
+            .filter_map(|profile| profile.url.as_ref());
+        emails(self.work_email.iter())
+            .chain(emails(self.recommended_personal_email.iter()))
+            .chain(emails(self.personal_emails.iter().flatten()))
+            .chain(emails(listed_emails))
+            .chain(profiles(self.linkedin_url.iter()))
+            .chain(profiles(self.facebook_url.iter()))
+            .chain(profiles(self.twitter_url.iter()))
+            .chain(profiles(self.github_url.iter()))
+            .chain(profiles(listed_profiles))
+            .collect()
+    }
+}
+
+impl StorableRecord for CompanyResponse {
+    const KIND: &'static str = "company";
+
+    fn record_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn record_dataset_version(&self) -> Option<&str> {
+        self.dataset_version.as_deref()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.website
+            .iter()
+            .chain(self.alternative_domains.iter().flatten())
+            .map(|website| Lookup::Website(website.clone()))
+            .chain(profiles(self.linkedin_url.iter()))
+            .chain(profiles(self.facebook_url.iter()))
+            .chain(profiles(self.twitter_url.iter()))
+            .chain(profiles(self.profiles.iter().flatten()))
+            .collect()
+    }
+}
+
+impl StorableRecord for JobPostingRecord {
+    const KIND: &'static str = "job_posting";
+
+    fn record_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Job postings don't carry a dataset version.
+    fn record_dataset_version(&self) -> Option<&str> {
+        None
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        Vec::new()
+    }
+}
+
+/// A record read from the store, with when it was fetched from PDL.
+#[derive(Debug)]
+pub struct StoredRecord<T> {
+    pub record: T,
+    pub dataset_version: Option<String>,
+    pub fetched_at: SystemTime,
+    /// False when the record was just fetched from the API by one of the
+    /// `enrich_with_store` methods rather than read from the store.
+    pub from_store: bool,
+}
+
+impl<T> StoredRecord<T> {
+    /// Whether the record was fetched no more than `max_age` ago.
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.fetched_at.elapsed().map_or(true, |age| age <= max_age)
+    }
+}
+
+fn store_error(error: impl std::fmt::Display) -> PDLError {
+    PDLError::StoreError(error.to_string())
+}
+
+/// A local SQLite copy of enriched records, keyed by record type and PDL
+/// id, that can be consulted before making billed calls. Records are also
+/// indexed by their emails and profile URLs (people) or websites and
+/// profile URLs (companies).
+pub struct RecordStore {
+    connection: Connection,
+}
+
+impl RecordStore {
+    /// Opens the store at `path`, creating the file and tables if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PDLError> {
+        Self::with_connection(Connection::open(path).map_err(store_error)?)
+    }
+
+    /// A store that lives only as long as the returned value.
+    pub fn open_in_memory() -> Result<Self, PDLError> {
+        Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, PDLError> {
+        connection.execute_batch(SCHEMA).map_err(store_error)?;
+        Ok(RecordStore { connection })
+    }
+
+    /// Inserts `record`, or replaces the stored record with the same id,
+    /// stamping it as fetched now. Returns false without storing anything
+    /// if the record has no id.
+    pub fn upsert<T: StorableRecord>(&self, record: &T) -> Result<bool, PDLError> {
+        self.upsert_fetched_at(record, SystemTime::now())
+    }
+
+    /// `upsert` with an explicit fetch time, e.g. when importing records
+    /// fetched earlier.
+    pub fn upsert_fetched_at<T: StorableRecord>(
+        &self,
+        record: &T,
+        fetched_at: SystemTime,
+    ) -> Result<bool, PDLError> {
+        let transaction = self
+            .connection
+            .unchecked_transaction()
+            .map_err(store_error)?;
+        let stored = write_record(&transaction, record, fetched_at)?;
+        transaction.commit().map_err(store_error)?;
+        Ok(stored)
+    }
+
+    /// Upserts every record in one transaction, returning how many had an
+    /// id and were stored.
+    pub fn upsert_all<T: StorableRecord>(&mut self, records: &[T]) -> Result<usize, PDLError> {
+        let fetched_at = SystemTime::now();
+        let transaction = self.connection.transaction().map_err(store_error)?;
+        let mut stored = 0;
+        for record in records {
+            stored += usize::from(write_record(&transaction, record, fetched_at)?);
+        }
+        transaction.commit().map_err(store_error)?;
+        Ok(stored)
+    }
+
+    /// The stored record with PDL id `id`.
+    pub fn get<T: StorableRecord>(&self, id: &str) -> Result<Option<StoredRecord<T>>, PDLError> {
+        self.connection
+            .query_row(
+                "SELECT body, dataset_version, fetched_at FROM records
+                 WHERE kind = ?1 AND id = ?2",
+                params![T::KIND, id],
+                read_row,
+            )
+            .optional()
+            .map_err(store_error)?
+            .transpose()
+    }
+
+    /// The most recently fetched record matching `lookup`.
+    pub fn find<T: StorableRecord>(
+        &self,
+        lookup: &Lookup,
+    ) -> Result<Option<StoredRecord<T>>, PDLError> {
+        let Some((key, value)) = lookup.key() else {
+            return Ok(None);
+        };
+        self.connection
+            .query_row(
+                "SELECT records.body, records.dataset_version, records.fetched_at
+                 FROM lookups JOIN records
+                     ON records.kind = lookups.kind AND records.id = lookups.id
+                 WHERE lookups.kind = ?1 AND lookups.key = ?2 AND lookups.value = ?3
+                 ORDER BY records.fetched_at DESC
+                 LIMIT 1",
+                params![T::KIND, key, value],
+                read_row,
+            )
+            .optional()
+            .map_err(store_error)?
+            .transpose()
+    }
+
+    /// The stored person matching `params` by `pdl_id`, then `email`, then
+    /// `profile`. Other params (name, company, location, ...) aren't
+    /// matched.
+    pub fn find_person(
+        &self,
+        params: &PersonParams,
+    ) -> Result<Option<StoredRecord<PersonRecord>>, PDLError> {
+        for id in params.pdl_id.iter().flatten() {
+            if let Some(stored) = self.get(id)? {
+                return Ok(Some(stored));
+            }
+        }
+        let lookups =
+            emails(params.email.iter().flatten()).chain(profiles(params.profile.iter().flatten()));
+        self.find_first(lookups)
+    }
+
+    /// The stored company matching `params` by `pdl_id`, then `website`,
+    /// then `profile`.
+    pub fn find_company(
+        &self,
+        params: &CompanyParams,
+    ) -> Result<Option<StoredRecord<CompanyResponse>>, PDLError> {
+        if let Some(id) = &params.pdl_id {
+            if let Some(stored) = self.get(id)? {
+                return Ok(Some(stored));
+            }
+        }
+        let lookups = params
+            .website
+            .iter()
+            .map(|website| Lookup::Website(website.clone()))
+            .chain(profiles(params.profile.iter()));
+        self.find_first(lookups)
+    }
+
+    fn find_first<T: StorableRecord>(
+        &self,
+        lookups: impl Iterator<Item = Lookup>,
+    ) -> Result<Option<StoredRecord<T>>, PDLError> {
+        for lookup in lookups {
+            if let Some(stored) = self.find(&lookup)? {
+                return Ok(Some(stored));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Removes the record with PDL id `id`, returning whether there was one.
+    pub fn remove<T: StorableRecord>(&self, id: &str) -> Result<bool, PDLError> {
+        self.connection
+            .execute(
+                "DELETE FROM lookups WHERE kind = ?1 AND id = ?2",
+                params![T::KIND, id],
+            )
+            .map_err(store_error)?;
+        let removed = self
+            .connection
+            .execute(
+                "DELETE FROM records WHERE kind = ?1 AND id = ?2",
+                params![T::KIND, id],
+            )
+            .map_err(store_error)?;
+        Ok(removed > 0)
+    }
+}
+
+/// Writes `record` and its lookups, replacing any stored copy.
+fn write_record<T: StorableRecord>(
+    connection: &Connection,
+    record: &T,
+    fetched_at: SystemTime,
+) -> Result<bool, PDLError> {
+    let Some(id) = record.record_id().filter(|id| !id.is_empty()) else {
+        return Ok(false);
+    };
+    let body = serde_json::to_string(record).map_err(|_| PDLError::SerializationError)?;
+
+    connection
+        .execute(
+            "INSERT INTO records (kind, id, dataset_version, fetched_at, body)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (kind, id) DO UPDATE SET
+                 dataset_version = excluded.dataset_version,
+                 fetched_at = excluded.fetched_at,
+                 body = excluded.body",
+            params![
+                T::KIND,
+                id,
+                record.record_dataset_version(),
+                unix_seconds(fetched_at) as i64,
+                body
+            ],
+        )
+        .map_err(store_error)?;
+    connection
+        .execute(
+            "DELETE FROM lookups WHERE kind = ?1 AND id = ?2",
+            params![T::KIND, id],
+        )
+        .map_err(store_error)?;
+    for (key, value) in record.lookups().iter().filter_map(Lookup::key) {
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO lookups (kind, key, value, id) VALUES (?1, ?2, ?3, ?4)",
+                params![T::KIND, key, value, id],
+            )
+            .map_err(store_error)?;
+    }
+    Ok(true)
+}
+
+fn read_row<T: DeserializeOwned>(row: &Row) -> rusqlite::Result<Result<StoredRecord<T>, PDLError>> {
+    let body: String = row.get(0)?;
+    let dataset_version: Option<String> = row.get(1)?;
+    let fetched_at: i64 = row.get(2)?;
+    Ok(serde_json::from_str(&body)
+        .map_err(|_| PDLError::SerializationError)
+        .map(|record| StoredRecord {
+            record,
+            dataset_version,
+            fetched_at: UNIX_EPOCH + Duration::from_secs(fetched_at.max(0) as u64),
+            from_store: true,
+        }))
+}
+
+impl Person {
+    /// Enriches a person unless `store` already holds a match for the
+    /// params' `pdl_id`, `email` or `profile` (see
+    /// `RecordStore::find_person`) fetched within `max_age`; `None` accepts
+    /// any stored match. A fetched record is upserted into the store.
+    pub fn enrich_with_store(
+        &self,
+        params: EnrichPersonParams,
+        store: &RecordStore,
+        max_age: Option<Duration>,
+    ) -> Result<StoredRecord<PersonRecord>, PDLError> {
+        let stored = store.find_person(&params.person_params)?;
+        if let Some(stored) = stored.filter(|stored| max_age.is_none_or(|age| stored.is_fresh(age)))
+        {
+            return Ok(stored);
+        }
+        let record = self.enrich(params)?.data;
+        fetched(store, record)
+    }
+}
+
+impl Company {
+    /// Enriches a company unless `store` already holds a match; see
+    /// `Person::enrich_with_store` and `RecordStore::find_company`.
+    pub fn enrich_with_store(
+        &self,
+        params: EnrichCompanyParams,
+        store: &RecordStore,
+        max_age: Option<Duration>,
+    ) -> Result<StoredRecord<CompanyResponse>, PDLError> {
+        let stored = store.find_company(&params.company_params)?;
+        if let Some(stored) = stored.filter(|stored| max_age.is_none_or(|age| stored.is_fresh(age)))
+        {
+            return Ok(stored);
+        }
+        let record = self.enrich(params)?;
+        fetched(store, record)
+    }
+}
+
+fn fetched<T: StorableRecord>(store: &RecordStore, record: T) -> Result<StoredRecord<T>, PDLError> {
+    let fetched_at = SystemTime::now();
+    store.upsert_fetched_at(&record, fetched_at)?;
+    Ok(StoredRecord {
+        dataset_version: record.record_dataset_version().map(str::to_string),
+        record,
+        fetched_at,
+        from_store: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn person(value: serde_json::Value) -> PersonRecord {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn company(value: serde_json::Value) -> CompanyResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn jane() -> PersonRecord {
+        person(json!({
+            "id": "jane-id",
+            "dataset_version": "29.0",
+            "full_name": "jane doe",
+            "work_email": "Jane.Doe@Acme.com",
+            "emails": [{"address": "jane@example.com"}],
+            "linkedin_url": "linkedin.com/in/janedoe",
+            "profiles": [{"url": "github.com/janedoe"}],
+        }))
+    }
+
+    #[test]
+    fn test_upsert_replaces_by_id() {
+        let store = RecordStore::open_in_memory().unwrap();
+        assert!(store.upsert(&jane()).unwrap());
+
+        let updated = person(json!({
+            "id": "jane-id",
+            "dataset_version": "30.0",
+            "full_name": "jane smith",
+            "work_email": "jane@newco.com",
+        }));
+        assert!(store.upsert(&updated).unwrap());
+
+        let stored = store.get::<PersonRecord>("jane-id").unwrap().unwrap();
+        assert_eq!(stored.record.full_name.as_deref(), Some("jane smith"));
+        assert_eq!(stored.dataset_version.as_deref(), Some("30.0"));
+        assert!(stored.from_store);
+        assert!(stored.is_fresh(Duration::from_secs(60)));
+
+        // Lookups of the replaced record are gone.
+        let old = store
+            .find::<PersonRecord>(&Lookup::Email("jane@example.com".to_string()))
+            .unwrap();
+        assert!(old.is_none());
+        let new = store
+            .find::<PersonRecord>(&Lookup::Email("JANE@newco.com".to_string()))
+            .unwrap();
+        assert!(new.is_some());
+    }
+
+    #[test]
+    fn test_records_without_id_are_not_stored() {
+        let mut store = RecordStore::open_in_memory().unwrap();
+        assert!(!store
+            .upsert(&person(json!({"full_name": "no id"})))
+            .unwrap());
+        let stored = store
+            .upsert_all(&[jane(), person(json!({"id": ""}))])
+            .unwrap();
+        assert_eq!(stored, 1);
+    }
+
+    #[test]
+    fn test_find_normalizes_lookups() {
+        let store = RecordStore::open_in_memory().unwrap();
+        store.upsert(&jane()).unwrap();
+        store
+            .upsert(&company(json!({
+                "id": "acme-id",
+                "website": "acme.com",
+                "alternative_domains": ["acme.io"],
+                "linkedin_url": "linkedin.com/company/acme",
+            })))
+            .unwrap();
+
+        for lookup in [
+            Lookup::Email("jane.doe@acme.com".to_string()),
+            Lookup::Email(" JANE@EXAMPLE.COM ".to_string()),
+            Lookup::Profile("https://www.linkedin.com/in/JaneDoe/".to_string()),
+            Lookup::Profile("https://github.com/janedoe".to_string()),
+        ] {
+            let found = store.find::<PersonRecord>(&lookup).unwrap();
+            assert_eq!(
+                found.unwrap().record.id.as_deref(),
+                Some("jane-id"),
+                "{:?}",
+                lookup
+            );
+        }
+
+        for lookup in [
+            Lookup::Website("https://www.ACME.com/about".to_string()),
+            Lookup::Website("acme.io".to_string()),
+            Lookup::Profile("linkedin.com/company/acme/".to_string()),
+        ] {
+            let found = store.find::<CompanyResponse>(&lookup).unwrap();
+            assert_eq!(
+                found.unwrap().record.id.as_deref(),
+                Some("acme-id"),
+                "{:?}",
+                lookup
+            );
+        }
+
+        // Kinds are kept apart.
+        let profile = Lookup::Profile("linkedin.com/in/janedoe".to_string());
+        assert!(store.find::<CompanyResponse>(&profile).unwrap().is_none());
+        assert!(store
+            .find::<PersonRecord>(&Lookup::Email("not an email".to_string()))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_person_and_company_from_params() {
+        let store = RecordStore::open_in_memory().unwrap();
+        store.upsert(&jane()).unwrap();
+        store
+            .upsert(&company(json!({"id": "acme-id", "website": "acme.com"})))
+            .unwrap();
+
+        let params = PersonParams {
+            email: Some(vec![
+                "unknown@example.com".to_string(),
+                "jane@example.com".to_string(),
+            ]),
+            ..PersonParams::default()
+        };
+        assert!(store.find_person(&params).unwrap().is_some());
+
+        let params = PersonParams {
+            pdl_id: Some(vec!["jane-id".to_string()]),
+            ..PersonParams::default()
+        };
+        assert!(store.find_person(&params).unwrap().is_some());
+
+        let params = PersonParams {
+            name: Some(vec!["jane doe".to_string()]),
+            ..PersonParams::default()
+        };
+        assert!(store.find_person(&params).unwrap().is_none());
+
+        let params = CompanyParams {
+            website: Some("http://acme.com".to_string()),
+            ..CompanyParams::default()
+        };
+        assert!(store.find_company(&params).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_freshness_uses_fetch_time() {
+        let store = RecordStore::open_in_memory().unwrap();
+        let week_ago = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+        store.upsert_fetched_at(&jane(), week_ago).unwrap();
+
+        let stored = store.get::<PersonRecord>("jane-id").unwrap().unwrap();
+        assert!(!stored.is_fresh(Duration::from_secs(24 * 60 * 60)));
+        assert!(stored.is_fresh(Duration::from_secs(30 * 24 * 60 * 60)));
+    }
+
+    #[test]
+    fn test_store_persists_and_removes() {
+        let path = std::env::temp_dir().join(format!(
+            "peopledatalabs-store-{}.sqlite",
+            std::process::id()
+        ));
+        {
+            let store = RecordStore::open(&path).unwrap();
+            store.upsert(&jane()).unwrap();
+        }
+        let store = RecordStore::open(&path).unwrap();
+        assert!(store.get::<PersonRecord>("jane-id").unwrap().is_some());
+        assert!(store.get::<CompanyResponse>("jane-id").unwrap().is_none());
+
+        assert!(store.remove::<PersonRecord>("jane-id").unwrap());
+        assert!(!store.remove::<PersonRecord>("jane-id").unwrap());
+        let lookup = Lookup::Email("jane@example.com".to_string());
+        assert!(store.find::<PersonRecord>(&lookup).unwrap().is_none());
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
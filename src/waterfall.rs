@@ -0,0 +1,337 @@
+//! Waterfall person enrichment: one identifier type at a time, strongest
+//! first.
+
+use reqwest::StatusCode;
+
+use crate::{
+    api::Person,
+    models::{
+        common::{AdditionalParams, BaseParams},
+        person::{EnrichPersonParams, EnrichPersonResponse, PersonField, PersonParams},
+    },
+    PDLError,
+};
+
+/// One attempt of a waterfall: the name it is reported under and the
+/// `PersonParams` fields it sends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaterfallStep {
+    pub name: String,
+    pub fields: Vec<PersonField>,
+}
+
+impl WaterfallStep {
+    pub fn new(name: impl Into<String>, fields: impl IntoIterator<Item = PersonField>) -> Self {
+        Self {
+            name: name.into(),
+            fields: fields.into_iter().collect(),
+        }
+    }
+
+    /// Profile, then email, then phone, then name with company and
+    /// location.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("profile", [PersonField::Profile, PersonField::Lid]),
+            Self::new("email", [PersonField::Email, PersonField::EmailHash]),
+            Self::new("phone", [PersonField::Phone]),
+            Self::new(
+                "name_company_location",
+                [
+                    PersonField::Name,
+                    PersonField::FirstName,
+                    PersonField::LastName,
+                    PersonField::Company,
+                    PersonField::Location,
+                    PersonField::StreetAddress,
+                    PersonField::Locality,
+                    PersonField::Region,
+                    PersonField::PostalCode,
+                ],
+            ),
+        ]
+    }
+
+    /// The step's fields of `source`, and nothing else.
+    fn params(&self, source: &PersonParams) -> PersonParams {
+        let mut params = PersonParams {
+            query_encoding: source.query_encoding.clone(),
+            ..PersonParams::default()
+        };
+        for field in &self.fields {
+            match field {
+                PersonField::PdlId => params.pdl_id.clone_from(&source.pdl_id),
+                PersonField::Name => params.name.clone_from(&source.name),
+                PersonField::FirstName => params.first_name.clone_from(&source.first_name),
+                PersonField::LastName => params.last_name.clone_from(&source.last_name),
+                PersonField::Location => params.location.clone_from(&source.location),
+                PersonField::StreetAddress => {
+                    params.street_address.clone_from(&source.street_address)
+                }
+                PersonField::Locality => params.locality.clone_from(&source.locality),
+                PersonField::Region => params.region.clone_from(&source.region),
+                PersonField::PostalCode => params.postal_code.clone_from(&source.postal_code),
+                PersonField::Company => params.company.clone_from(&source.company),
+                PersonField::School => params.school.clone_from(&source.school),
+                PersonField::Phone => params.phone.clone_from(&source.phone),
+                PersonField::Email => params.email.clone_from(&source.email),
+                PersonField::EmailHash => params.email_hash.clone_from(&source.email_hash),
+                PersonField::Profile => params.profile.clone_from(&source.profile),
+                PersonField::Lid => params.lid.clone_from(&source.lid),
+            }
+        }
+        params
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WaterfallOptions {
+    /// Tried in order until one matches.
+    pub steps: Vec<WaterfallStep>,
+    /// Matches below this likelihood don't count. Also sent as
+    /// `min_likelihood`, so the API returns them as (free) 404s. Defaults
+    /// to 6.
+    pub min_likelihood: i32,
+    pub base_params: Option<BaseParams>,
+    /// Sent with every attempt; `min_likelihood` is overridden.
+    pub additional_params: Option<AdditionalParams>,
+}
+
+impl Default for WaterfallOptions {
+    fn default() -> Self {
+        Self {
+            steps: WaterfallStep::defaults(),
+            min_likelihood: 6,
+            base_params: None,
+            additional_params: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaterfallOutcome {
+    /// The step's fields aren't enough for PDL to match on, so it wasn't
+    /// sent.
+    Skipped,
+    NotFound,
+    /// A match below `min_likelihood`.
+    BelowThreshold,
+    Matched,
+}
+
+/// What one step of a waterfall did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaterfallAttempt {
+    pub step: String,
+    pub outcome: WaterfallOutcome,
+    pub likelihood: Option<i32>,
+    /// The response came from the client's cache or ledger rather than the
+    /// API.
+    pub replayed: bool,
+    /// Credits the attempt is billed: one for a 200 from the API, none for
+    /// a 404, a skipped step or a replayed response.
+    pub credits: u32,
+}
+
+#[derive(Debug)]
+pub struct WaterfallResult {
+    /// The first match at or above `min_likelihood`.
+    pub response: Option<EnrichPersonResponse>,
+    /// The step that produced `response`.
+    pub matched_step: Option<String>,
+    /// Every step tried, in order, up to and including the match.
+    pub attempts: Vec<WaterfallAttempt>,
+}
+
+impl WaterfallResult {
+    /// Credits billed across every attempt.
+    pub fn credits(&self) -> u32 {
+        self.attempts.iter().map(|attempt| attempt.credits).sum()
+    }
+}
+
+/// Runs the steps of `options` against `source` with `enrich`, which also
+/// tells whether its response was replayed, stopping at the first match at
+/// or above `min_likelihood`. Errors other than 404 stop the waterfall.
+fn run<F>(
+    source: &PersonParams,
+    options: &WaterfallOptions,
+    mut enrich: F,
+) -> Result<WaterfallResult, PDLError>
+where
+    F: FnMut(EnrichPersonParams) -> Result<(EnrichPersonResponse, bool), PDLError>,
+{
+    let additional_params = AdditionalParams {
+        min_likelihood: Some(options.min_likelihood),
+        ..options.additional_params.clone().unwrap_or_default()
+    };
+    let mut result = WaterfallResult {
+        response: None,
+        matched_step: None,
+        attempts: Vec::new(),
+    };
+
+    for step in &options.steps {
+        let person_params = step.params(source);
+        let mut attempt = WaterfallAttempt {
+            step: step.name.clone(),
+            outcome: WaterfallOutcome::Skipped,
+            likelihood: None,
+            replayed: false,
+            credits: 0,
+        };
+        if person_params.validate().is_err() {
+            result.attempts.push(attempt);
+            continue;
+        }
+
+        let params = EnrichPersonParams {
+            base_params: options.base_params.clone(),
+            person_params,
+            additional_params: Some(additional_params.clone()),
+        };
+        match enrich(params) {
+            Ok((response, replayed)) => {
+                attempt.likelihood = Some(response.likelihood);
+                attempt.replayed = replayed;
+                attempt.credits = u32::from(!replayed);
+                if response.likelihood >= options.min_likelihood {
+                    attempt.outcome = WaterfallOutcome::Matched;
+                    result.attempts.push(attempt);
+                    result.response = Some(response);
+                    result.matched_step = Some(step.name.clone());
+                    break;
+                }
+                attempt.outcome = WaterfallOutcome::BelowThreshold;
+            }
+            Err(PDLError::HTTPError(StatusCode::NOT_FOUND)) => {
+                attempt.outcome = WaterfallOutcome::NotFound;
+            }
+            Err(error) => return Err(error),
+        }
+        result.attempts.push(attempt);
+    }
+
+    Ok(result)
+}
+
+impl Person {
+    /// Enriches a person one identifier type at a time: each step of
+    /// `options.steps` sends only its fields of `params`, and the first
+    /// match at or above `options.min_likelihood` ends the waterfall. Steps
+    /// without enough input are skipped unsent. The result reports the
+    /// matching step and what every attempt returned and cost; responses
+    /// served from the client's cache or ledger cost nothing.
+    pub fn enrich_waterfall(
+        &self,
+        params: &PersonParams,
+        options: &WaterfallOptions,
+    ) -> Result<WaterfallResult, PDLError> {
+        run(params, options, |params| self.enrich_traced(params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn response(likelihood: i32) -> EnrichPersonResponse {
+        serde_json::from_value(json!({
+            "status": 200,
+            "likelihood": likelihood,
+            "data": {"full_name": "sean thorne"},
+        }))
+        .expect("ERROR")
+    }
+
+    fn contact() -> PersonParams {
+        PersonParams {
+            profile: Some(vec!["linkedin.com/in/seanthorne".to_string()]),
+            email: Some(vec!["sean@example.com".to_string()]),
+            name: Some(vec!["Sean Thorne".to_string()]),
+            company: Some(vec!["People Data Labs".to_string()]),
+            ..PersonParams::default()
+        }
+    }
+
+    #[test]
+    fn test_waterfall_stops_at_first_good_match() {
+        let mut sent = Vec::new();
+        let result = run(&contact(), &WaterfallOptions::default(), |params| {
+            sent.push(serde_json::to_value(&params).expect("ERROR"));
+            match sent.len() {
+                1 => Err(PDLError::HTTPError(StatusCode::NOT_FOUND)),
+                2 => Ok((response(4), false)),
+                _ => Ok((response(9), true)),
+            }
+        })
+        .expect("ERROR");
+
+        // Each attempt sends only its own fields.
+        assert_eq!(
+            sent,
+            vec![
+                json!({"profile": ["linkedin.com/in/seanthorne"], "min_likelihood": 6}),
+                json!({"email": ["sean@example.com"], "min_likelihood": 6}),
+                json!({
+                    "name": ["Sean Thorne"],
+                    "company": ["People Data Labs"],
+                    "min_likelihood": 6,
+                }),
+            ]
+        );
+        let outcomes: Vec<(&str, WaterfallOutcome, bool, u32)> = result
+            .attempts
+            .iter()
+            .map(|attempt| {
+                (
+                    attempt.step.as_str(),
+                    attempt.outcome,
+                    attempt.replayed,
+                    attempt.credits,
+                )
+            })
+            .collect();
+        // The match was replayed, so only the email attempt is billed.
+        assert_eq!(
+            outcomes,
+            vec![
+                ("profile", WaterfallOutcome::NotFound, false, 0),
+                ("email", WaterfallOutcome::BelowThreshold, false, 1),
+                ("phone", WaterfallOutcome::Skipped, false, 0),
+                ("name_company_location", WaterfallOutcome::Matched, true, 0),
+            ]
+        );
+        assert_eq!(
+            result.matched_step.as_deref(),
+            Some("name_company_location")
+        );
+        assert_eq!(result.credits(), 1);
+        assert_eq!(result.response.expect("ERROR").likelihood, 9);
+    }
+
+    #[test]
+    fn test_waterfall_without_match() {
+        let options = WaterfallOptions {
+            steps: vec![WaterfallStep::new("phone", [PersonField::Phone])],
+            ..WaterfallOptions::default()
+        };
+        let result = run(&contact(), &options, |_| Ok((response(9), false))).expect("ERROR");
+        assert!(result.response.is_none());
+        assert_eq!(result.attempts[0].outcome, WaterfallOutcome::Skipped);
+        assert_eq!(result.credits(), 0);
+    }
+
+    #[test]
+    fn test_waterfall_stops_on_errors() {
+        let result = run(&contact(), &WaterfallOptions::default(), |_| {
+            Err(PDLError::HTTPError(StatusCode::TOO_MANY_REQUESTS))
+        });
+        assert!(matches!(
+            result,
+            Err(PDLError::HTTPError(StatusCode::TOO_MANY_REQUESTS))
+        ));
+    }
+}